@@ -0,0 +1,185 @@
+// advisor.rs
+use crate::bot::enemy_neighbors;
+use crate::game::GameState;
+use crate::player::Player;
+use crate::turn_phase::TurnPhase;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacementAdvice {
+    pub territory: String,
+    pub armies: u16,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReinforcementAdvice {
+    pub player_id: usize,
+    pub reinforcement_armies: u16,
+    pub placements: Vec<PlacementAdvice>,
+}
+
+// What a border territory is worth holding or attacking from
+struct BorderInfo {
+    territory: String,
+    armies: u16,
+    enemy_armies: u16,
+    strongest_enemy: (String, u16),
+    // Continent bonus weighted by how much of the continent the player owns, either the
+    // territory's own continent or one it can attack into
+    continent_value: f64,
+    reasons: Vec<String>,
+}
+
+impl BorderInfo {
+    // Value of one more army when `placed` armies were already advised here. Enemy pressure
+    // falls off as the stack grows, so armies spread over the fronts that need them most.
+    fn marginal_value(&self, placed: u16) -> f64 {
+        let stack = (self.armies + placed) as f64;
+        let pressure = self.enemy_armies as f64 / stack;
+        let shortfall = if stack <= self.strongest_enemy.1 as f64 {
+            1.0
+        } else {
+            0.0
+        };
+        (1.0 + self.continent_value) * (pressure + shortfall)
+    }
+}
+
+// Split the current player's reinforcement armies over their borders, one army at a time
+// to the border where it adds the most value
+pub fn advise_reinforcements(state: &GameState) -> Result<ReinforcementAdvice, String> {
+    if state.turn_phase != TurnPhase::Reinforce {
+        return Err("Reinforcement advice is only available in the reinforce phase".to_string());
+    }
+
+    let player = &state.players[state.current_turn];
+    let mut borders: Vec<BorderInfo> = player
+        .territories
+        .iter()
+        .filter_map(|territory| border_info(state, player, territory))
+        .collect();
+    borders.sort_by(|a, b| a.territory.cmp(&b.territory));
+
+    let mut placed = vec![0u16; borders.len()];
+    for _ in 0..state.reinforcement_armies {
+        let best = (0..borders.len()).max_by(|&a, &b| {
+            borders[a]
+                .marginal_value(placed[a])
+                .total_cmp(&borders[b].marginal_value(placed[b]))
+        });
+        match best {
+            Some(index) => placed[index] += 1,
+            None => break,
+        }
+    }
+
+    let mut placements: Vec<PlacementAdvice> = borders
+        .into_iter()
+        .zip(placed)
+        .filter(|&(_, armies)| armies > 0)
+        .map(|(border, armies)| PlacementAdvice {
+            territory: border.territory,
+            armies,
+            reasons: border.reasons,
+        })
+        .collect();
+    placements.sort_by(|a, b| b.armies.cmp(&a.armies).then(a.territory.cmp(&b.territory)));
+
+    Ok(ReinforcementAdvice {
+        player_id: state.current_turn,
+        reinforcement_armies: state.reinforcement_armies,
+        placements,
+    })
+}
+
+fn border_info(state: &GameState, player: &Player, territory: &str) -> Option<BorderInfo> {
+    let enemies = enemy_neighbors(state, player, territory);
+    let strongest_enemy = enemies.iter().max_by_key(|(_, armies)| *armies)?.clone();
+    let weakest_enemy = enemies.iter().min_by_key(|(_, armies)| *armies)?.clone();
+    let enemy_armies: u16 = enemies.iter().map(|(_, armies)| armies).sum();
+    let armies = player.get_armies(territory);
+
+    let mut reasons = vec![format!(
+        "Borders {} enemy territories holding {} armies",
+        enemies.len(),
+        enemy_armies
+    )];
+    if strongest_enemy.1 >= armies {
+        reasons.push(format!(
+            "Threatened by {} armies in {}, only {} here",
+            strongest_enemy.1, strongest_enemy.0, armies
+        ));
+    }
+
+    let mut continent_value = 0.0;
+    if let Some((name, owned, total, bonus)) = continent_progress(state, player, territory) {
+        continent_value = bonus as f64 * owned as f64 / total as f64;
+        if owned == total {
+            reasons.push(format!(
+                "Defends {}, which is held completely for {} bonus armies",
+                name, bonus
+            ));
+        } else if owned * 2 >= total {
+            reasons.push(format!(
+                "Holds {} of {} territories in {}, worth {} bonus armies when complete",
+                owned, total, name, bonus
+            ));
+        }
+    }
+
+    // Attacking into a continent the player has mostly taken
+    for (enemy, _) in &enemies {
+        let Some((name, owned, total, bonus)) = continent_progress(state, player, enemy) else {
+            continue;
+        };
+        let value = bonus as f64 * owned as f64 / total as f64;
+        if owned * 2 >= total && value > continent_value {
+            continent_value = value;
+            reasons.push(format!(
+                "Can attack {} to complete {} ({} of {} owned, {} bonus armies)",
+                enemy, name, owned, total, bonus
+            ));
+        }
+    }
+
+    if weakest_enemy.1 < armies {
+        reasons.push(format!(
+            "Already outnumbers {} with {} armies against {}",
+            weakest_enemy.0, armies, weakest_enemy.1
+        ));
+    }
+
+    Some(BorderInfo {
+        territory: territory.to_string(),
+        armies,
+        enemy_armies,
+        strongest_enemy,
+        continent_value,
+        reasons,
+    })
+}
+
+// Continent of the territory with the number of its territories the player owns, the total
+// and the bonus
+fn continent_progress(
+    state: &GameState,
+    player: &Player,
+    territory: &str,
+) -> Option<(String, usize, usize, u16)> {
+    let continent = state
+        .board
+        .get_territory(territory)
+        .and_then(|t| state.board.get_continent(&t.continent))?;
+    let owned = continent
+        .territories
+        .iter()
+        .filter(|t| player.territories.contains(*t))
+        .count();
+    Some((
+        continent.name.clone(),
+        owned,
+        continent.territories.len(),
+        continent.bonus_armies,
+    ))
+}
@@ -0,0 +1,505 @@
+// battle.rs
+use crate::game::{Game, GameState};
+use crate::turn_phase::TurnPhase;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+// A single roll as played by Game::attack: the attacker rolls up to 3 dice but must leave one
+// army behind, the defender rolls up to 2, the highest dice are compared in pairs and
+// ties go to the defender.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RollOutcome {
+    pub attacker_losses: u16,
+    pub defender_losses: u16,
+    pub probability: f64,
+}
+
+// Probabilities of the possible losses of one roll, found by going through every throw
+pub fn roll_outcomes(attacker_dice: u16, defender_dice: u16) -> Vec<RollOutcome> {
+    let throws = |dice: u16| {
+        (0..dice)
+            .map(|_| 1..=6u16)
+            .multi_cartesian_product()
+            .map(|mut roll| {
+                roll.sort_unstable_by(|a, b| b.cmp(a));
+                roll
+            })
+            .collect::<Vec<_>>()
+    };
+    let attacker_throws = throws(attacker_dice);
+    let defender_throws = throws(defender_dice);
+    let total = (attacker_throws.len() * defender_throws.len()) as f64;
+
+    let compared = attacker_dice.min(defender_dice) as usize;
+    let mut counts = vec![0usize; compared + 1];
+    for attack in &attacker_throws {
+        for defend in &defender_throws {
+            let defender_losses = attack.iter().zip(defend).filter(|(a, d)| a > d).count();
+            counts[defender_losses] += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .map(|(defender_losses, count)| RollOutcome {
+            attacker_losses: (compared - defender_losses) as u16,
+            defender_losses: defender_losses as u16,
+            probability: count as f64 / total,
+        })
+        .collect()
+}
+
+// Probability that the attacker conquers the territory when attacking with all dice until
+// one side is exhausted, for every attacker <= max_attacker and defender <= max_defender.
+// Indexed [attacker][defender], filled bottom-up so any size takes O(attacker * defender).
+pub fn conquer_probabilities(max_attacker: u16, max_defender: u16) -> Vec<Vec<f64>> {
+    let mut table = vec![];
+    extend_conquer_probabilities(&mut table, max_attacker, max_defender, |_, _| {});
+    table
+}
+
+// Grow a table from conquer_probabilities to at least max_attacker x max_defender, only
+// computing the cells that are new. progress is called with (rows done, total rows).
+pub fn extend_conquer_probabilities(
+    table: &mut Vec<Vec<f64>>,
+    max_attacker: u16,
+    max_defender: u16,
+    mut progress: impl FnMut(usize, usize),
+) {
+    let rolls = roll_table();
+    let known_rows = table.len();
+    let known_columns = table.first().map(|row| row.len()).unwrap_or(0);
+    let a_max = (max_attacker as usize).max(known_rows.saturating_sub(1));
+    let d_max = (max_defender as usize).max(known_columns.saturating_sub(1));
+
+    table.resize(a_max + 1, vec![]);
+    for a in 0..=a_max {
+        let first_new = if a < known_rows { known_columns } else { 0 };
+        table[a].resize(d_max + 1, 0.0);
+        for d in first_new..=d_max {
+            table[a][d] = conquer_cell(&rolls, a, d, |a, d| table[a][d]);
+        }
+        progress(a + 1, a_max + 1);
+    }
+}
+
+// Single cell of conquer_probabilities. A roll costs the attacker at most two armies, so only
+// the last three rows are kept and memory stays O(defender).
+pub fn conquer_probability(attacker_armies: u16, defender_armies: u16) -> f64 {
+    let rolls = roll_table();
+    let d_max = defender_armies as usize;
+    let mut rows = vec![vec![0.0; d_max + 1]; 3];
+    for a in 0..=attacker_armies as usize {
+        let mut row = vec![0.0; d_max + 1];
+        for d in 0..=d_max {
+            // A roll that costs the attacker nothing stays in the row being filled
+            row[d] = conquer_cell(&rolls, a, d, |from, to| {
+                if from == a {
+                    row[to]
+                } else {
+                    rows[from % 3][to]
+                }
+            });
+        }
+        rows[a % 3] = row;
+    }
+    rows[attacker_armies as usize % 3][d_max]
+}
+
+// Probability of conquering with a attackers against d defenders, given the cells it depends on
+fn conquer_cell(
+    rolls: &[Vec<Vec<RollOutcome>>],
+    a: usize,
+    d: usize,
+    known: impl Fn(usize, usize) -> f64,
+) -> f64 {
+    if a <= 1 {
+        0.0
+    } else if d == 0 {
+        1.0
+    } else {
+        rolls[(a - 1).min(3)][d.min(2)]
+            .iter()
+            .map(|roll| {
+                roll.probability
+                    * known(
+                        a - roll.attacker_losses as usize,
+                        d - roll.defender_losses as usize,
+                    )
+            })
+            .sum()
+    }
+}
+
+// Roll outcomes indexed [attacker dice][defender dice]
+fn roll_table() -> Vec<Vec<Vec<RollOutcome>>> {
+    (0..=3)
+        .map(|attacker_dice| {
+            (0..=2)
+                .map(|defender_dice| {
+                    if attacker_dice == 0 || defender_dice == 0 {
+                        vec![]
+                    } else {
+                        roll_outcomes(attacker_dice, defender_dice)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndState {
+    pub attacker_armies: u16,
+    pub defender_armies: u16,
+    pub probability: f64,
+}
+
+// Everything that can come out of attacking until the defender is wiped out or the attacker
+// is down to the one army that has to stay behind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BattleOutcome {
+    pub attacker_armies: u16,
+    pub defender_armies: u16,
+    pub max_dice: u16,
+    pub win_probability: f64,
+    // Indexed by the number of armies left in the attacking territory, 1 to attacker_armies
+    // (index 0 is always 0)
+    pub attacker_remaining: Vec<f64>,
+    // Indexed by the number of armies left in the defending territory, 0 to defender_armies
+    pub defender_remaining: Vec<f64>,
+    pub expected_attacker_losses: f64,
+    pub expected_defender_losses: f64,
+    pub end_states: Vec<EndState>,
+}
+
+// Probability of every end state of battles against defender_armies, where start[a] is the
+// probability that the attack starts with a armies. Indexed [attacker][defender].
+fn battle_mass(start: &[f64], defender_armies: u16, max_dice: u16) -> Vec<Vec<f64>> {
+    let rolls = roll_table();
+
+    // Every roll removes at least one army, so going through the states from the largest
+    // total downwards sees all mass flowing into a state before it flows out
+    let a_max = start.len().saturating_sub(1);
+    let d_max = defender_armies as usize;
+    let mut mass = vec![vec![0.0f64; d_max + 1]; a_max + 1];
+    for (a, &p) in start.iter().enumerate() {
+        mass[a][d_max] = p;
+    }
+    for total in (0..=a_max + d_max).rev() {
+        for a in 2..=a_max {
+            if a > total || total - a > d_max || total - a == 0 {
+                continue;
+            }
+            let d = total - a;
+            let p = mass[a][d];
+            if p == 0.0 {
+                continue;
+            }
+            mass[a][d] = 0.0;
+            let attacker_dice = (a - 1).min(max_dice as usize);
+            let defender_dice = d.min(2);
+            for roll in &rolls[attacker_dice][defender_dice] {
+                let next_a = a - roll.attacker_losses as usize;
+                let next_d = d - roll.defender_losses as usize;
+                mass[next_a][next_d] += p * roll.probability;
+            }
+        }
+    }
+    mass
+}
+
+// Distribution of the battle, attacking with at most max_dice dice every roll
+pub fn battle_outcome(
+    attacker_armies: u16,
+    defender_armies: u16,
+    max_dice: u16,
+) -> Result<BattleOutcome, String> {
+    if attacker_armies == 0 {
+        return Err("The attacker needs at least one army".to_string());
+    }
+    if !(1..=3).contains(&max_dice) {
+        return Err("Attacks use 1 to 3 dice".to_string());
+    }
+
+    let (a_max, d_max) = (attacker_armies as usize, defender_armies as usize);
+    let mut start = vec![0.0; a_max + 1];
+    start[a_max] = 1.0;
+    let mass = battle_mass(&start, defender_armies, max_dice);
+
+    let mut end_states = vec![];
+    let mut attacker_remaining = vec![0.0; a_max + 1];
+    let mut defender_remaining = vec![0.0; d_max + 1];
+    for (a, row) in mass.iter().enumerate() {
+        for (d, &p) in row.iter().enumerate() {
+            if p == 0.0 {
+                continue;
+            }
+            attacker_remaining[a] += p;
+            defender_remaining[d] += p;
+            end_states.push(EndState {
+                attacker_armies: a as u16,
+                defender_armies: d as u16,
+                probability: p,
+            });
+        }
+    }
+
+    let expected = |remaining: &[f64], start: usize| {
+        remaining
+            .iter()
+            .enumerate()
+            .map(|(armies, p)| (start - armies) as f64 * p)
+            .sum::<f64>()
+    };
+
+    Ok(BattleOutcome {
+        attacker_armies,
+        defender_armies,
+        max_dice,
+        win_probability: defender_remaining[0],
+        expected_attacker_losses: expected(&attacker_remaining, a_max),
+        expected_defender_losses: expected(&defender_remaining, d_max),
+        attacker_remaining,
+        defender_remaining,
+        end_states,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathStep {
+    pub territory: String,
+    pub defender_armies: u16,
+    // Probability of having conquered the path up to and including this territory
+    pub conquer_probability: f64,
+    // Armies expected to move into this territory when it is conquered
+    pub expected_armies: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathOutcome {
+    pub from: String,
+    pub attacker_armies: u16,
+    pub steps: Vec<PathStep>,
+    pub conquer_probability: f64,
+    // Armies expected in the last territory when the whole path is conquered
+    pub expected_armies_remaining: f64,
+    // Probability of ending in the last territory with each number of armies, unconditional
+    pub final_armies: Vec<f64>,
+}
+
+// Chain of attacks where after each conquest every army but the one that has to stay
+// behind moves forward and attacks the next territory. Returns per step the probability of
+// getting that far, the expected armies moved in and their distribution.
+fn path_outcome(
+    attacker_armies: u16,
+    defenders: &[u16],
+    max_dice: u16,
+) -> Result<Vec<(f64, f64, Vec<f64>)>, String> {
+    if !(1..=3).contains(&max_dice) {
+        return Err("Attacks use 1 to 3 dice".to_string());
+    }
+
+    let mut stack = vec![0.0; attacker_armies as usize + 1];
+    stack[attacker_armies as usize] = 1.0;
+    let mut steps = vec![];
+    for &defender_armies in defenders {
+        let mass = battle_mass(&stack, defender_armies, max_dice);
+        let mut moved = vec![0.0; stack.len()];
+        for (a, row) in mass.iter().enumerate().skip(2) {
+            moved[a - 1] += row[0];
+        }
+        let conquer_probability: f64 = moved.iter().sum();
+        let expected_armies = if conquer_probability > 0.0 {
+            moved
+                .iter()
+                .enumerate()
+                .map(|(armies, p)| armies as f64 * p)
+                .sum::<f64>()
+                / conquer_probability
+        } else {
+            0.0
+        };
+        stack = moved;
+        steps.push((conquer_probability, expected_armies, stack.clone()));
+    }
+    Ok(steps)
+}
+
+// Plan a chain of attacks on the current board. The owner of `from` attacks every
+// territory of `path` in order, each one adjacent to the previous.
+pub fn plan_attack_path(
+    state: &GameState,
+    from: &str,
+    path: &[String],
+    max_dice: u16,
+) -> Result<PathOutcome, String> {
+    if path.is_empty() {
+        return Err("The path needs at least one territory".to_string());
+    }
+    let attacker = state
+        .players
+        .iter()
+        .find(|p| p.territories.contains(from))
+        .ok_or(format!("Nobody owns {}", from))?;
+
+    let mut previous = from;
+    let mut defenders = vec![];
+    for (index, territory) in path.iter().enumerate() {
+        if path[..index].contains(territory) {
+            return Err(format!("{} is in the path twice", territory));
+        }
+        let adjacent = state
+            .board
+            .get_territory(previous)
+            .map(|t| t.is_adjacent(territory))
+            .unwrap_or(false);
+        if !adjacent {
+            return Err(format!("{} is not adjacent to {}", territory, previous));
+        }
+        let defender = state
+            .players
+            .iter()
+            .find(|p| p.territories.contains(territory))
+            .ok_or(format!("Nobody owns {}", territory))?;
+        if defender.id == attacker.id {
+            return Err(format!("{} already belongs to {}", territory, attacker.name));
+        }
+        defenders.push(defender.get_armies(territory));
+        previous = territory;
+    }
+
+    let attacker_armies = attacker.get_armies(from);
+    let outcomes = path_outcome(attacker_armies, &defenders, max_dice)?;
+    let steps: Vec<PathStep> = path
+        .iter()
+        .zip(&defenders)
+        .zip(&outcomes)
+        .map(|((territory, &defender_armies), (probability, expected, _))| PathStep {
+            territory: territory.clone(),
+            defender_armies,
+            conquer_probability: *probability,
+            expected_armies: *expected,
+        })
+        .collect();
+    let (conquer_probability, expected_armies_remaining, final_armies) = outcomes
+        .last()
+        .cloned()
+        .unwrap_or_default();
+
+    Ok(PathOutcome {
+        from: from.to_string(),
+        attacker_armies,
+        steps,
+        conquer_probability,
+        expected_armies_remaining,
+        final_armies,
+    })
+}
+
+// A game in the attack phase where the current player owns `from` next to an enemy `to`, for
+// playing battles through Game::attack
+pub fn attack_setup(seed: u64) -> (Game, String, String) {
+    let mut game = Game::new_seeded(None, Some(3), seed);
+    let mut territories: Vec<&String> = game.board.territories.keys().collect();
+    territories.sort();
+
+    let owner = |territory: &str| {
+        game.players
+            .iter()
+            .position(|p| p.territories.contains(territory))
+            .expect("Every territory has an owner")
+    };
+    let (from, to) = territories
+        .iter()
+        .find_map(|from| {
+            let mut adjacent: Vec<&String> = game.board.territories[*from]
+                .adjacent_territories
+                .iter()
+                .collect();
+            adjacent.sort();
+            adjacent
+                .into_iter()
+                .find(|to| owner(to) != owner(from))
+                .map(|to| ((*from).clone(), to.clone()))
+        })
+        .expect("The board has territories owned by different players");
+
+    game.current_turn = owner(&from);
+    game.turn_phase = TurnPhase::Attack;
+    (game, from, to)
+}
+
+// Share of seeded battles through Game::attack, attacking until one side is exhausted, that
+// conquer `to`. template comes from attack_setup.
+pub fn attack_conquest_rate(
+    template: &Game,
+    from: &str,
+    to: &str,
+    attacker: u16,
+    defender: u16,
+    battles: usize,
+    seed: u64,
+) -> Result<f64, String> {
+    let attacker_id = template.current_turn;
+    let defender_id = template
+        .players
+        .iter()
+        .position(|p| p.territories.contains(to))
+        .expect("Defending territory has an owner");
+
+    let mut setup = template.clone();
+    setup.players[attacker_id].set_armies(from, attacker);
+    setup.players[defender_id].set_armies(to, defender);
+
+    let mut game = setup.clone();
+    let mut conquered = 0;
+    for battle in 0..battles {
+        game.players.clone_from(&setup.players);
+        game.turn_phase = TurnPhase::Attack;
+        game.conquered_territory = false;
+        game.reseed(seed ^ ((attacker as u64) << 48) ^ ((defender as u64) << 32) ^ battle as u64);
+
+        game.attack(attacker_id, from, to, 3, true)
+            .map_err(|e| format!("Attack {} vs {} failed: {}", attacker, defender, e))?;
+        if game.players[attacker_id].territories.contains(to) {
+            conquered += 1;
+        }
+    }
+    Ok(conquered as f64 / battles as f64)
+}
+
+// Binomial standard error of a conquest rate over that many battles, with a floor so that
+// certain outcomes still allow for a stray battle
+pub fn conquest_rate_standard_error(expected: f64, battles: usize) -> f64 {
+    let n = battles as f64;
+    (expected * (1.0 - expected) / n).sqrt().max(1.0 / n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The model has to match how Game::attack actually plays out
+    #[test]
+    fn attack_matches_conquer_probability() {
+        let battles = 2000;
+        let (game, from, to) = attack_setup(0);
+        for (attacker, defender) in [(2, 1), (3, 2), (4, 4), (6, 3), (8, 10), (12, 9)] {
+            let expected = conquer_probability(attacker, defender);
+            let observed =
+                attack_conquest_rate(&game, &from, &to, attacker, defender, battles, 0).unwrap();
+            let tolerance = 4.0 * conquest_rate_standard_error(expected, battles);
+            assert!(
+                (observed - expected).abs() <= tolerance,
+                "{} vs {}: expected {:.4}, observed {:.4}",
+                attacker,
+                defender,
+                expected,
+                observed
+            );
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use risk_board_game_server::bot::BotRegistry;
+use risk_board_game_server::engine::ENGINES_FILE;
+use risk_board_game_server::map::MapDefinition;
+use risk_board_game_server::map_analysis::{
+    analyze_map, analyze_map_with_self_play, MapAnalysis, SelfPlayConfig,
+};
+
+const USAGE: &str = "Usage: analyze_map <name|file> [--games <n>] [--bots <bot,bot,...>] \
+    [--seed <n>] [--max-rounds <n>] [--json]";
+
+struct Args {
+    map: String,
+    // Without games only the board structure is analyzed
+    self_play: SelfPlayConfig,
+    json: bool,
+}
+
+// Format: cargo run --release --bin analyze_map <name|file> [options]
+fn parse_args() -> Result<Args, String> {
+    let mut map = None;
+    let mut self_play = SelfPlayConfig {
+        bots: vec!["greedy".to_string(); 3],
+        games: 0,
+        seed: 0,
+        max_rounds: 500,
+    };
+    let mut json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--games" | "--bots" | "--seed" | "--max-rounds" => {
+                let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+                let invalid = || format!("Invalid value for {}: {}", arg, value);
+                match arg.as_str() {
+                    "--games" => self_play.games = value.parse().map_err(|_| invalid())?,
+                    "--bots" => self_play.bots = value.split(',').map(str::to_string).collect(),
+                    "--seed" => self_play.seed = value.parse().map_err(|_| invalid())?,
+                    _ => self_play.max_rounds = value.parse().map_err(|_| invalid())?,
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+            _ if map.is_none() => map = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(Args {
+        map: map.ok_or(USAGE)?,
+        self_play,
+        json,
+    })
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let map = match MapDefinition::resolve(&args.map, None) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Failed to load map: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let analysis = if args.self_play.games == 0 {
+        analyze_map(&map)
+    } else {
+        let registry = BotRegistry::with_engines_file(ENGINES_FILE);
+        match analyze_map_with_self_play(&map, &args.self_play, &registry) {
+            Ok(analysis) => analysis,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&analysis).expect("Analysis serializes to JSON")
+        );
+    } else {
+        print_analysis(&analysis);
+    }
+}
+
+fn print_analysis(analysis: &MapAnalysis) {
+    println!(
+        "{}: {} territories, {} continents",
+        analysis.map,
+        analysis.territories,
+        analysis.continents.len()
+    );
+    if analysis.games > 0 {
+        println!(
+            "Self-play: {} games, {} draws",
+            analysis.games, analysis.draws
+        );
+    }
+    println!();
+
+    println!(
+        "{:<24}{:>6}{:>8}{:>8}{:>8}{:>10}{:>11}{:>9}{:>9}{:>10}",
+        "Continent",
+        "Bonus",
+        "Size",
+        "Border",
+        "Entry",
+        "Pressure",
+        "Structural",
+        "Held",
+        "Value",
+        "Suggested"
+    );
+    for continent in &analysis.continents {
+        let (held, value) = match &continent.self_play {
+            Some(self_play) => (
+                format!("{:.1}%", self_play.hold_rate * 100.0),
+                format!("{:.2}", self_play.value),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{:<24}{:>6}{:>8}{:>8}{:>8}{:>10.2}{:>11}{:>9}{:>9}{:>10}",
+            continent.name,
+            continent.bonus_armies,
+            continent.territories,
+            continent.border_territories,
+            continent.entry_points,
+            continent.neighbor_pressure,
+            continent.structural_bonus,
+            held,
+            value,
+            continent.suggested_bonus
+        );
+    }
+}
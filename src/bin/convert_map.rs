@@ -0,0 +1,82 @@
+use risk_board_game_server::map::MapDefinition;
+
+const USAGE: &str = "Usage: convert_map <input> <output> [--name <name>] [--force]";
+
+struct Args {
+    input: String,
+    output: String,
+    name: Option<String>,
+    // Write the output even if the map does not pass validation
+    force: bool,
+}
+
+// Format: cargo run --bin convert_map <input> <output> [options]
+// The format of each file follows its extension: .map for Domination maps, JSON otherwise
+fn parse_args() -> Result<Args, String> {
+    let mut positional = vec![];
+    let mut name = None;
+    let mut force = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--force" => force = true,
+            "--name" => name = Some(args.next().ok_or("Missing value for --name")?),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+            _ => positional.push(arg),
+        }
+    }
+    match <[String; 2]>::try_from(positional) {
+        Ok([input, output]) => Ok(Args {
+            input,
+            output,
+            name,
+            force,
+        }),
+        Err(_) => Err(USAGE.to_string()),
+    }
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut map = match MapDefinition::read_from_file(&args.input) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Failed to read map: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(name) = args.name {
+        map.name = name;
+    }
+
+    let errors = map.validate();
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+    if !errors.is_empty() && !args.force {
+        eprintln!(
+            "{} problems found, use --force to convert anyway",
+            errors.len()
+        );
+        std::process::exit(1);
+    }
+
+    if let Err(e) = map.save_to_file(&args.output) {
+        eprintln!("Failed to write map: {}", e);
+        std::process::exit(1);
+    }
+    println!(
+        "Converted {} ({} territories, {} continents) to {}",
+        map.name,
+        map.territories.len(),
+        map.continents.len(),
+        args.output
+    );
+}
@@ -0,0 +1,68 @@
+use risk_board_game_server::map_generator::{generate_map, GeneratorConfig};
+
+const USAGE: &str = "Usage: generate_map [--territories <n>] [--continents <n>] [--seed <n>] \
+    [--output <path>]";
+
+struct Args {
+    generator: GeneratorConfig,
+    output: Option<String>,
+}
+
+// Format: cargo run --bin generate_map [options]
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args {
+        generator: GeneratorConfig::default(),
+        output: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or(format!("Missing value for {}\n{}", arg, USAGE))?;
+        let invalid = || format!("Invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--territories" => {
+                parsed.generator.territories = value.parse().map_err(|_| invalid())?
+            }
+            "--continents" => parsed.generator.continents = value.parse().map_err(|_| invalid())?,
+            "--seed" => parsed.generator.seed = Some(value.parse().map_err(|_| invalid())?),
+            "--output" => parsed.output = Some(value),
+            _ => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let map = match generate_map(&args.generator) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // JSON on stdout, or a file whose extension picks the format
+    match &args.output {
+        Some(output) => {
+            if let Err(e) = map.save_to_file(output) {
+                eprintln!("Failed to write map: {}", e);
+                std::process::exit(1);
+            }
+            println!("{} written to {}", map.name, output);
+        }
+        None => println!(
+            "{}",
+            serde_json::to_string_pretty(&map).expect("Maps serialize to JSON")
+        ),
+    }
+}
@@ -1,196 +1,225 @@
-use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs::File;
+use risk_board_game_server::battle::extend_conquer_probabilities;
+use risk_board_game_server::probability_table::ProbabilityTable;
+use std::fmt::Write as _;
 use std::io::Write;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
 use std::time::Instant;
 
+const USAGE: &str = "Usage: precompute_conquest_probabilities [<max_attack> <max_defend>] \
+    [--output <path>] [--format bin|json|csv] [--precision <0-15>] [--quiet] [--progress] \
+    [--extend <table.bin>]\n\
+    --extend only accepts full precision tables, rounded ones such as the embedded table are \
+    refused";
+
+// Decimal places an f64 holds, rounding to more only adds noise
+const MAX_PRECISION: u32 = 15;
+
+#[derive(PartialEq)]
+enum Format {
+    Bin,
+    Json,
+    Csv,
+}
+
 struct Args {
     max_attack_armies: u16,
     max_defend_armies: u16,
+    output: Option<String>,
+    format: Format,
+    // Decimal places kept, None keeps full precision
+    precision: Option<u32>,
+    quiet: bool,
+    progress: bool,
+    extend: Option<String>,
 }
 
-// Parse command line arguments to get max army counts
-lazy_static::lazy_static! {
-    static ref ARGS: Args = {
-        let args: Vec<String> = std::env::args().collect();
+// Format: cargo run --release --bin precompute_conquest_probabilities <max_attack> <max_defend> [options]
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args {
+        max_attack_armies: 100,
+        max_defend_armies: 100,
+        output: None,
+        format: Format::Bin,
+        precision: None,
+        quiet: false,
+        progress: false,
+        extend: None,
+    };
 
-        // Default values if no args provided
-        let mut max_attack = 100;
-        let mut max_defend = 100;
+    let mut positional = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--quiet" => parsed.quiet = true,
+            "--progress" => parsed.progress = true,
+            "--output" | "--format" | "--precision" | "--extend" => {
+                let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+                match arg.as_str() {
+                    "--output" => parsed.output = Some(value),
+                    "--format" => {
+                        parsed.format = match value.as_str() {
+                            "bin" => Format::Bin,
+                            "json" => Format::Json,
+                            "csv" => Format::Csv,
+                            _ => return Err(format!("Unknown format {}\n{}", value, USAGE)),
+                        }
+                    }
+                    "--precision" => {
+                        let digits: u32 = value
+                            .parse()
+                            .map_err(|_| format!("Invalid precision: {}", value))?;
+                        if digits > MAX_PRECISION {
+                            return Err(format!(
+                                "Precision is limited to {} digits, more than an f64 holds\n{}",
+                                MAX_PRECISION, USAGE
+                            ));
+                        }
+                        parsed.precision = Some(digits)
+                    }
+                    _ => parsed.extend = Some(value),
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+            _ => positional.push(arg),
+        }
+    }
 
-        // Parse args (format: cargo run --bin precompute_conquest_probabilities <max_attack> <max_defend>)
-        if args.len() > 2 {
-            max_attack = args[1].parse().unwrap_or(100);
-            max_defend = args[2].parse().unwrap_or(100);
+    match positional.as_slice() {
+        [] => {}
+        [attack, defend] => {
+            parsed.max_attack_armies = attack
+                .parse()
+                .map_err(|_| format!("Invalid attacker armies: {}", attack))?;
+            parsed.max_defend_armies = defend
+                .parse()
+                .map_err(|_| format!("Invalid defender armies: {}", defend))?;
         }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(parsed)
+}
 
-        Args {
-            max_attack_armies: max_attack,
-            max_defend_armies: max_defend,
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
     };
-}
+    let start = Instant::now();
 
-fn calculate_attack_probability(
-    attacker_armies: u32,
-    defender_armies: u32,
-    prob_cache: &Arc<Mutex<HashMap<(u16, u16), f64>>>,
-) -> f64 {
-    // Check if the probability is already in the cache
-    if let Some(&prob) = prob_cache
-        .lock()
-        .unwrap()
-        .get(&(attacker_armies as u16, defender_armies as u16))
-    {
-        return prob;
-    }
+    // Start from an existing table so only the new rows and columns are computed
+    let mut rows = match &args.extend {
+        Some(path) => match ProbabilityTable::load_from_file(path) {
+            Ok(table) if table.is_rounded() => {
+                eprintln!(
+                    "{} is rounded, extend a full precision table instead (without --precision \
+                     or with more than 4 digits)",
+                    path
+                );
+                std::process::exit(1);
+            }
+            Ok(table) => {
+                if !args.quiet {
+                    println!(
+                        "Extending {}x{} table from {}",
+                        table.max_attacker(),
+                        table.max_defender(),
+                        path
+                    );
+                }
+                table.rows()
+            }
+            Err(e) => {
+                eprintln!("Failed to load table to extend: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => vec![],
+    };
 
-    if attacker_armies <= 1 {
-        return 0.0;
-    }
-    if defender_armies == 0 {
-        return 1.0;
+    let show_progress = args.progress && !args.quiet;
+    extend_conquer_probabilities(
+        &mut rows,
+        args.max_attack_armies,
+        args.max_defend_armies,
+        |done, total| {
+            if show_progress && (done % 10 == 0 || done == total) {
+                print_progress(done, total);
+            }
+        },
+    );
+    if show_progress {
+        println!();
     }
 
-    let a = attacker_armies as usize;
-    let d = defender_armies as usize;
-
-    let prob = if a >= 3 && d >= 2 {
-        let p_win2 = p_win2(a);
-        let p_lose2 = p_lose2(a);
-        let p_win1_lose1 = 1.0 - p_win2 - p_lose2;
-
-        p_win2 * calculate_attack_probability(a as u32, d as u32 - 2, prob_cache)
-            + p_win1_lose1 * calculate_attack_probability(a as u32 - 1, d as u32 - 1, prob_cache)
-            + p_lose2 * calculate_attack_probability(a as u32 - 2, d as u32, prob_cache)
-    } else {
-        let p_win1 = p_win1(a, d);
+    if let Some(digits) = args.precision {
+        let scale = 10f64.powi(digits as i32);
+        for value in rows.iter_mut().flatten() {
+            *value = (*value * scale).round() / scale;
+        }
+    }
 
-        p_win1 * calculate_attack_probability(a as u32, d as u32 - 1, prob_cache)
-            + (1.0 - p_win1) * calculate_attack_probability(a as u32 - 1, d as u32, prob_cache)
+    let output = args.output.clone().unwrap_or_else(|| {
+        match args.format {
+            Format::Bin => "conquer_probabilities.bin",
+            Format::Json => "conquer_probabilities.json",
+            Format::Csv => "conquer_probabilities.csv",
+        }
+        .to_string()
+    });
+    let result = match args.format {
+        // Four digits or fewer fit the compact rounded encoding
+        Format::Bin => ProbabilityTable::from_rows(&rows, args.precision.is_some_and(|d| d <= 4))
+            .save_to_file(&output),
+        Format::Json => write_json(&output, &rows),
+        Format::Csv => write_csv(&output, &rows),
     };
-
-    // Store the calculated probability in the cache
-    prob_cache
-        .lock()
-        .unwrap()
-        .insert((attacker_armies as u16, defender_armies as u16), prob);
-
-    prob
-}
-
-fn dice_distribution(n: usize) -> Vec<(usize, usize)> {
-    match n {
-        1 => (1..=6).map(|i| (1, i)).collect(),
-        2 => (1..=6).flat_map(|i| (i..=6).map(move |j| (i, j))).collect(),
-        3 => (1..=6)
-            .flat_map(|i| (i..=6).flat_map(move |j| (j..=6).map(move |k| (j, k))))
-            .collect(),
-        _ => panic!("Invalid number of dice"),
+    if let Err(e) = result {
+        eprintln!("Failed to write probability table: {}", e);
+        std::process::exit(1);
     }
-}
 
-fn p_win2(a: usize) -> f64 {
-    let attacker_dist = dice_distribution(std::cmp::min(a - 1, 3));
-    let defender_dist = dice_distribution(2);
-    let total = attacker_dist.len() * defender_dist.len();
-    let wins = attacker_dist
-        .iter()
-        .flat_map(|&(a1, a2)| {
-            defender_dist
-                .iter()
-                .filter(move |&&(d1, d2)| a1 > d1 && a2 > d2)
-        })
-        .count();
-    wins as f64 / total as f64
+    if !args.quiet {
+        println!(
+            "Probability table for {}x{} successfully written to {}",
+            rows.len() - 1,
+            rows[0].len() - 1,
+            output
+        );
+        println!("Total computation time: {:?}", start.elapsed());
+    }
 }
 
-fn p_lose2(a: usize) -> f64 {
-    let attacker_dist = dice_distribution(std::cmp::min(a - 1, 3));
-    let defender_dist = dice_distribution(2);
-    let total = attacker_dist.len() * defender_dist.len();
-    let losses = attacker_dist
-        .iter()
-        .flat_map(|&(a1, a2)| {
-            defender_dist
-                .iter()
-                .filter(move |&&(d1, d2)| a1 <= d1 && a2 <= d2)
-        })
-        .count();
-    losses as f64 / total as f64
+fn print_progress(done: usize, total: usize) {
+    const WIDTH: usize = 40;
+    let filled = done * WIDTH / total;
+    print!(
+        "\r[{}{}] {}/{} attacker rows",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        done,
+        total
+    );
+    let _ = std::io::stdout().flush();
 }
 
-fn p_win1(a: usize, d: usize) -> f64 {
-    let attacker_dist = dice_distribution(std::cmp::min(a - 1, 3));
-    let defender_dist = dice_distribution(std::cmp::min(d, 2));
-    let total = attacker_dist.len() * defender_dist.len();
-    let wins = attacker_dist
-        .iter()
-        .flat_map(|&(_, a2)| defender_dist.iter().filter(move |&&(_, d2)| a2 > d2))
-        .count();
-    wins as f64 / total as f64
+fn write_json(output: &str, rows: &[Vec<f64>]) -> Result<(), String> {
+    let json = serde_json::json!({
+        "max_attacker": rows.len() - 1,
+        "max_defender": rows[0].len() - 1,
+        "probabilities": rows,
+    });
+    let data = serde_json::to_string(&json).map_err(|e| e.to_string())?;
+    std::fs::write(output, data).map_err(|e| format!("{}: {}", output, e))
 }
 
-fn calculate_conquer_probability(
-    attacker_armies: u16,
-    defender_armies: u16,
-    prob_cache: &Arc<Mutex<HashMap<(u16, u16), f64>>>,
-) -> f64 {
-    let start = Instant::now();
-    // Call the recursive function to calculate probability
-    let probability =
-        calculate_attack_probability(attacker_armies as u32, defender_armies as u32, prob_cache);
-    let rounded_probability = (probability * 10000.0).round() / 100.0;
-    let time = start.elapsed();
-    if time > Duration::new(10, 0) {
-        println!("Computation time: {:?}", time);
+fn write_csv(output: &str, rows: &[Vec<f64>]) -> Result<(), String> {
+    let mut csv = "attacker,defender,probability\n".to_string();
+    for (attacker, row) in rows.iter().enumerate() {
+        for (defender, probability) in row.iter().enumerate() {
+            let _ = writeln!(csv, "{},{},{}", attacker, defender, probability);
+        }
     }
-    rounded_probability
-}
-
-#[derive(Serialize, Deserialize)]
-struct ProbabilityCache {
-    cache: HashMap<(u16, u16), f64>,
-}
-
-fn main() {
-    let start = Instant::now();
-
-    // Create a shared, thread-safe cache
-    let prob_cache = Arc::new(Mutex::new(HashMap::new()));
-
-    // Create a parallel iterator to compute the probabilities in parallel
-    let prob_cache_clone = prob_cache.clone();
-    (2..=ARGS.max_attack_armies)
-        .flat_map(|attacker_armies| {
-            (1..=ARGS.max_defend_armies)
-                .map(move |defender_armies| (attacker_armies, defender_armies))
-        })
-        .collect::<Vec<(u16, u16)>>()
-        .par_iter()
-        .for_each(|&(attacker_armies, defender_armies)| {
-            println!(
-                "{:?} attacker vs. {:?} defender",
-                attacker_armies, defender_armies
-            );
-            let _ =
-                calculate_conquer_probability(attacker_armies, defender_armies, &prob_cache_clone);
-        });
-
-    // After all computations are done, lock the cache and write it to a file
-    let probability_cache = ProbabilityCache {
-        cache: prob_cache.lock().unwrap().clone(),
-    };
-
-    // Serialize the cache to a file
-    let mut file = File::create("conquer_probabilities.bin").expect("Failed to create file");
-    let encoded: Vec<u8> =
-        bincode::serialize(&probability_cache).expect("Failed to serialize data");
-    file.write_all(&encoded).expect("Failed to write to file");
-
-    println!("Probability cache successfully written to conquer_probabilities.bin");
-    println!("Total computation time: {:?}", start.elapsed());
+    std::fs::write(output, csv).map_err(|e| format!("{}: {}", output, e))
 }
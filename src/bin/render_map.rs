@@ -0,0 +1,75 @@
+use risk_board_game_server::map::MapDefinition;
+
+const USAGE: &str = "Usage: render_map <name|file> [--format dot|svg] [--output <path>]";
+
+struct Args {
+    map: String,
+    svg: bool,
+    output: Option<String>,
+}
+
+// Format: cargo run --bin render_map <name|file> [options]
+fn parse_args() -> Result<Args, String> {
+    let mut map = None;
+    let mut svg = None;
+    let mut output = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "--output" => {
+                let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+                match (arg.as_str(), value.as_str()) {
+                    ("--format", "dot") => svg = Some(false),
+                    ("--format", "svg") => svg = Some(true),
+                    ("--format", _) => return Err(format!("Invalid value for {}: {}", arg, value)),
+                    _ => output = Some(value),
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+            _ if map.is_none() => map = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    // Without --format the extension of the output decides
+    let svg = svg.unwrap_or(output.as_deref().is_some_and(|path| path.ends_with(".svg")));
+    Ok(Args {
+        map: map.ok_or(USAGE)?,
+        svg,
+        output,
+    })
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let map = match MapDefinition::resolve(&args.map, None) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Failed to load map: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let board = map.to_board();
+    let rendered = if args.svg {
+        board.to_svg(&[])
+    } else {
+        board.to_dot(&[])
+    };
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, rendered) {
+                eprintln!("Failed to write {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", rendered),
+    }
+}
@@ -0,0 +1,235 @@
+use itertools::Itertools;
+use rayon::prelude::*;
+use risk_board_game_server::bot::{BotRegistry, Seat};
+use risk_board_game_server::engine::ENGINES_FILE;
+use risk_board_game_server::game::Game;
+use risk_board_game_server::game_config::GameConfig;
+use risk_board_game_server::map::MapDefinition;
+use risk_board_game_server::map_generator::{generate_map, GeneratorConfig};
+use risk_board_game_server::simulation::{play_game, GameResult};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+// Games still running after this many rounds are counted as a draw
+const MAX_ROUNDS: usize = 500;
+
+const USAGE: &str = "Usage: simulate <num_games> <bot> <bot> [<bot> ...] [--map <name|file>] \
+    [--generate] [--territories <n>] [--continents <n>] [--seed <n>]";
+
+struct Args {
+    num_games: usize,
+    bots: Vec<String>,
+    // Built-in map name or map file, the classic map by default
+    map: Option<String>,
+    // Play every game on a new generated map, seeded with seed + game index
+    generate: bool,
+    generator: GeneratorConfig,
+    seed: u64,
+}
+
+// Format: cargo run --release --bin simulate <num_games> <bot> <bot> [<bot> ...] [options]
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args {
+        num_games: 0,
+        bots: vec![],
+        map: None,
+        generate: false,
+        generator: GeneratorConfig::default(),
+        seed: 0,
+    };
+
+    let mut positional = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--generate" => parsed.generate = true,
+            "--map" | "--territories" | "--continents" | "--seed" => {
+                let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+                let invalid = || format!("Invalid value for {}: {}", arg, value);
+                match arg.as_str() {
+                    "--map" => parsed.map = Some(value),
+                    "--territories" => {
+                        parsed.generator.territories = value.parse().map_err(|_| invalid())?
+                    }
+                    "--continents" => {
+                        parsed.generator.continents = value.parse().map_err(|_| invalid())?
+                    }
+                    _ => parsed.seed = value.parse().map_err(|_| invalid())?,
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() < 3 {
+        return Err(USAGE.to_string());
+    }
+    if parsed.generate && parsed.map.is_some() {
+        return Err("Give either --map or --generate".to_string());
+    }
+    parsed.num_games = positional[0]
+        .parse()
+        .map_err(|_| format!("Invalid number of games: {}", positional[0]))?;
+    parsed.bots = positional[1..].to_vec();
+    Ok(parsed)
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let registry = BotRegistry::with_engines_file(ENGINES_FILE);
+
+    let seats: Vec<Seat> = args.bots.iter().map(|name| Seat::Bot(name.clone())).collect();
+    if let Err(e) = registry.create_seats(&seats) {
+        eprintln!("{}. Available bots: {}", e, registry.names().join(", "));
+        std::process::exit(1);
+    }
+
+    let num_players = seats.len();
+    let config = match &args.map {
+        Some(map) => match MapDefinition::resolve(map, None) {
+            Ok(definition) => match definition.starting_armies_for(num_players) {
+                Ok(_) => Some(GameConfig::from_definition(definition)),
+                Err(e) => {
+                    eprintln!("Can't play on {}: {}", map, e);
+                    std::process::exit(1);
+                }
+            },
+            Err(e) => {
+                eprintln!("Failed to load map: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    if args.generate && args.generator.territories < num_players {
+        eprintln!(
+            "{} players don't fit on {} territories",
+            num_players, args.generator.territories
+        );
+        std::process::exit(1);
+    }
+
+    let start = Instant::now();
+    // Errors are reported after the join, so the other games still finish
+    let outcomes: Vec<Result<GameResult, String>> = (0..args.num_games)
+        .into_par_iter()
+        .map(|index| {
+            let mut bots: Vec<_> = args
+                .bots
+                .iter()
+                .map(|name| registry.create(name).expect("Bot was validated"))
+                .collect();
+            let config = if args.generate {
+                let generator = GeneratorConfig {
+                    seed: Some(args.seed.wrapping_add(index as u64)),
+                    ..args.generator.clone()
+                };
+                let map = generate_map(&generator).map_err(|e| format!("Game {}: {}", index, e))?;
+                Some(GameConfig::from_definition(map))
+            } else {
+                config.clone()
+            };
+            let mut game = Game::try_new(config, Some(num_players))
+                .map_err(|e| format!("Game {}: {}", index, e))?;
+            Ok(play_game(&mut game, &mut bots, MAX_ROUNDS))
+        })
+        .collect();
+
+    let (results, errors): (Vec<_>, Vec<_>) = outcomes.into_iter().partition_result();
+    for e in &errors {
+        eprintln!("{}", e);
+    }
+    if !results.is_empty() {
+        print_stats(&args.bots, &results);
+    }
+    println!("Total simulation time: {:?}", start.elapsed());
+    if !errors.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn print_stats(bots: &[String], results: &[GameResult]) {
+    let num_players = bots.len();
+    let finished: Vec<&GameResult> = results.iter().filter(|r| r.winner.is_some()).collect();
+    let draws = results.len() - finished.len();
+    let average_rounds = if finished.is_empty() {
+        0.0
+    } else {
+        finished.iter().map(|r| r.rounds as f64).sum::<f64>() / finished.len() as f64
+    };
+
+    println!(
+        "Played {} games, {} draws after {} rounds",
+        results.len(),
+        draws,
+        MAX_ROUNDS
+    );
+    println!("Average rounds of finished games: {:.1}", average_rounds);
+    println!();
+
+    println!(
+        "{:<6}{:<16}{:>6}{:>10}{:>11}  Eliminated as 1st, 2nd, ...",
+        "Seat", "Bot", "Wins", "Win rate", "Avg place"
+    );
+    for (seat, bot) in bots.iter().enumerate() {
+        let wins = results.iter().filter(|r| r.winner == Some(seat)).count();
+        let average_place = results
+            .iter()
+            .map(|r| r.placement(seat, num_players) as f64)
+            .sum::<f64>()
+            / results.len().max(1) as f64;
+        let eliminations: Vec<String> = (0..num_players - 1)
+            .map(|position| {
+                results
+                    .iter()
+                    .filter(|r| r.elimination_order.get(position) == Some(&seat))
+                    .count()
+                    .to_string()
+            })
+            .collect();
+        println!(
+            "{:<6}{:<16}{:>6}{:>9.1}%{:>11.2}  {}",
+            seat,
+            bot,
+            wins,
+            percentage(wins, results.len()),
+            average_place,
+            eliminations.join(", ")
+        );
+    }
+    println!();
+
+    // Bots can occupy several seats, so aggregate them separately
+    let mut per_bot: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for (seat, bot) in bots.iter().enumerate() {
+        let entry = per_bot.entry(bot).or_default();
+        entry.0 += results.len();
+        entry.1 += results.iter().filter(|r| r.winner == Some(seat)).count();
+    }
+    println!("{:<16}{:>8}{:>6}{:>10}", "Bot", "Seats", "Wins", "Win rate");
+    for (bot, (seats_played, wins)) in per_bot {
+        println!(
+            "{:<16}{:>8}{:>6}{:>9.1}%",
+            bot,
+            seats_played,
+            wins,
+            percentage(wins, seats_played)
+        );
+    }
+}
+
+fn percentage(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
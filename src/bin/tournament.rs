@@ -0,0 +1,187 @@
+use itertools::Itertools;
+use risk_board_game_server::bot::BotRegistry;
+use risk_board_game_server::engine::ENGINES_FILE;
+use risk_board_game_server::tournament::{
+    run_tournament, Format, TournamentConfig, TournamentResult,
+};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: tournament [--format round-robin|swiss] [--rounds <n>] \
+    [--games <n>] [--players <n>] [--seed <n>] [--max-rounds <n>] [--output <dir>] \
+    <bot> <bot> [<bot> ...]";
+
+struct Args {
+    config: TournamentConfig,
+    output: String,
+}
+
+// Format: cargo run --release --bin tournament [options] <bot> <bot> [<bot> ...]
+fn parse_args() -> Result<Args, String> {
+    let mut format = "round-robin".to_string();
+    let mut rounds = 5;
+    let mut games_per_match = 0;
+    let mut players_per_game = 3;
+    let mut seed = 0;
+    let mut max_rounds = 500;
+    let mut output = "tournament".to_string();
+    let mut bots = vec![];
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            bots.push(arg);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or(format!("Missing value for {}", arg))?;
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid value for {}: {}", arg, value))
+        };
+        match arg.as_str() {
+            "--format" => format = value.clone(),
+            "--rounds" => rounds = number()?,
+            "--games" => games_per_match = number()?,
+            "--players" => players_per_game = number()?,
+            "--seed" => seed = number()? as u64,
+            "--max-rounds" => max_rounds = number()?,
+            "--output" => output = value.clone(),
+            _ => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+        }
+    }
+
+    if bots.len() < 2 {
+        return Err(USAGE.to_string());
+    }
+    let format = match format.as_str() {
+        "round-robin" => Format::RoundRobin,
+        "swiss" => Format::Swiss { rounds },
+        _ => return Err(format!("Unknown format {}\n{}", format, USAGE)),
+    };
+    // By default every table plays each seating once
+    if games_per_match == 0 {
+        games_per_match = players_per_game;
+    }
+
+    Ok(Args {
+        config: TournamentConfig {
+            format,
+            bots,
+            players_per_game,
+            games_per_match,
+            seed,
+            max_rounds,
+        },
+        output,
+    })
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let registry = BotRegistry::with_engines_file(ENGINES_FILE);
+
+    let start = Instant::now();
+    let result = match run_tournament(&args.config, &registry) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}. Available bots: {}", e, registry.names().join(", "));
+            std::process::exit(1);
+        }
+    };
+
+    print_leaderboard(&result);
+    if let Err(e) = write_results(&args.output, &result) {
+        eprintln!("Failed to write results to {}: {}", args.output, e);
+        std::process::exit(1);
+    }
+    println!("Results written to {}/", args.output);
+    println!("Total tournament time: {:?}", start.elapsed());
+}
+
+fn print_leaderboard(result: &TournamentResult) {
+    println!("Played {} games", result.games.len());
+    println!();
+    println!(
+        "{:<6}{:<16}{:>8}{:>7}{:>6}{:>7}{:>11}",
+        "Rank", "Bot", "Rating", "Games", "Wins", "Draws", "Avg place"
+    );
+    for (rank, standing) in result.leaderboard.iter().enumerate() {
+        println!(
+            "{:<6}{:<16}{:>8.0}{:>7}{:>6}{:>7}{:>11.2}",
+            rank + 1,
+            standing.bot,
+            standing.rating,
+            standing.games,
+            standing.wins,
+            standing.draws,
+            standing.average_placement
+        );
+    }
+}
+
+fn write_results(output: &str, result: &TournamentResult) -> std::io::Result<()> {
+    let dir = Path::new(output);
+    fs::create_dir_all(dir)?;
+
+    fs::write(
+        dir.join("leaderboard.json"),
+        serde_json::to_string_pretty(&result.leaderboard)?,
+    )?;
+    fs::write(
+        dir.join("games.json"),
+        serde_json::to_string_pretty(&result.games)?,
+    )?;
+
+    let mut leaderboard = "rank,bot,rating,games,wins,draws,average_placement\n".to_string();
+    for (rank, standing) in result.leaderboard.iter().enumerate() {
+        leaderboard.push_str(&format!(
+            "{},{},{:.1},{},{},{},{:.3}\n",
+            rank + 1,
+            csv_field(&standing.bot),
+            standing.rating,
+            standing.games,
+            standing.wins,
+            standing.draws,
+            standing.average_placement
+        ));
+    }
+    fs::write(dir.join("leaderboard.csv"), leaderboard)?;
+
+    // Seats and placements are joined with ';' to keep one row per game
+    let mut games = "game,round,seed,seats,winner,rounds,actions,placements\n".to_string();
+    for record in &result.games {
+        games.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            record.index,
+            record.round,
+            record.seed,
+            csv_field(&record.seats.join(";")),
+            csv_field(record.winner().unwrap_or("")),
+            record.result.rounds,
+            record.result.actions,
+            record.placements().iter().join(";")
+        ));
+    }
+    fs::write(dir.join("games.csv"), games)?;
+    Ok(())
+}
+
+// Engine names come from engines.json and may contain separators or quotes
+fn csv_field(value: &str) -> String {
+    if value.contains([',', ';', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
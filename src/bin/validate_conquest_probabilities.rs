@@ -0,0 +1,170 @@
+use rayon::prelude::*;
+use risk_board_game_server::battle::{
+    attack_conquest_rate, attack_setup, conquest_rate_standard_error,
+};
+use risk_board_game_server::probability_table::{ProbabilityTable, ROUNDED_SCALE};
+use std::sync::Arc;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: validate_conquest_probabilities [--max-attacker <n>] \
+    [--max-defender <n>] [--battles <n>] [--seed <n>] [--z <score>] [--table <table.bin>]";
+
+struct Args {
+    max_attacker: u16,
+    max_defender: u16,
+    battles: usize,
+    seed: u64,
+    // Standard errors the observed win rate may be off before a pair is flagged
+    z: f64,
+    table: Option<String>,
+}
+
+struct PairResult {
+    attacker: u16,
+    defender: u16,
+    expected: f64,
+    observed: f64,
+    tolerance: f64,
+}
+
+// Format: cargo run --release --bin validate_conquest_probabilities [options]
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args {
+        max_attacker: 20,
+        max_defender: 20,
+        battles: 2000,
+        seed: 0,
+        z: 4.0,
+        table: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("Missing value for {}\n{}", arg, USAGE))?;
+        let invalid = || format!("Invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--max-attacker" => parsed.max_attacker = value.parse().map_err(|_| invalid())?,
+            "--max-defender" => parsed.max_defender = value.parse().map_err(|_| invalid())?,
+            "--battles" => parsed.battles = value.parse().map_err(|_| invalid())?,
+            "--seed" => parsed.seed = value.parse().map_err(|_| invalid())?,
+            "--z" => parsed.z = value.parse().map_err(|_| invalid())?,
+            "--table" => parsed.table = Some(value),
+            _ => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+        }
+    }
+    // Without battles or pairs nothing would be checked and the run would still pass
+    if parsed.battles == 0 {
+        return Err(format!("--battles has to be at least 1\n{}", USAGE));
+    }
+    if parsed.max_attacker < 2 || parsed.max_defender < 1 {
+        return Err(format!(
+            "Attacks need --max-attacker 2 or more and --max-defender 1 or more\n{}",
+            USAGE
+        ));
+    }
+    Ok(parsed)
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let start = Instant::now();
+
+    let (mut template, from, to) = attack_setup(args.seed);
+    let mut rounding = 0.0;
+    if let Some(path) = &args.table {
+        match ProbabilityTable::load_from_file(path) {
+            Ok(table) => {
+                if table.is_rounded() {
+                    rounding = 0.5 / ROUNDED_SCALE;
+                }
+                template.set_probability_table(Arc::new(table));
+            }
+            Err(e) => {
+                eprintln!("Failed to load probability table: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else if ProbabilityTable::embedded().is_rounded() {
+        rounding = 0.5 / ROUNDED_SCALE;
+    }
+
+    let pairs: Vec<(u16, u16)> = (2..=args.max_attacker)
+        .flat_map(|a| (1..=args.max_defender).map(move |d| (a, d)))
+        .collect();
+    // Failed attacks are reported after the join, so the other pairs still finish
+    let outcomes: Vec<Result<PairResult, String>> = pairs
+        .par_iter()
+        .map(|&(attacker, defender)| {
+            let expected = template.clone().calculate_conquer_probability(attacker, defender);
+            let observed = attack_conquest_rate(
+                &template,
+                &from,
+                &to,
+                attacker,
+                defender,
+                args.battles,
+                args.seed,
+            )?;
+            Ok(PairResult {
+                attacker,
+                defender,
+                expected,
+                observed,
+                tolerance: args.z * conquest_rate_standard_error(expected, args.battles)
+                    + rounding,
+            })
+        })
+        .collect();
+    let mut results = vec![];
+    for outcome in outcomes {
+        match outcome {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    results.sort_by_key(|r| (r.attacker, r.defender));
+
+    let flagged: Vec<&PairResult> = results
+        .iter()
+        .filter(|r| (r.observed - r.expected).abs() > r.tolerance)
+        .collect();
+
+    println!(
+        "Checked {} pairs up to {}x{} with {} battles each through Game::attack",
+        results.len(),
+        args.max_attacker,
+        args.max_defender,
+        args.battles
+    );
+    if flagged.is_empty() {
+        println!("All pairs are within {} standard errors", args.z);
+    } else {
+        println!();
+        println!(
+            "{:>9}{:>9}{:>10}{:>10}{:>11}",
+            "Attacker", "Defender", "Expected", "Observed", "Tolerance"
+        );
+        for r in &flagged {
+            println!(
+                "{:>9}{:>9}{:>10.4}{:>10.4}{:>11.4}",
+                r.attacker, r.defender, r.expected, r.observed, r.tolerance
+            );
+        }
+        println!();
+        println!("{} pairs differ from the probability model", flagged.len());
+    }
+    println!("Total validation time: {:?}", start.elapsed());
+
+    if !flagged.is_empty() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,85 @@
+use risk_board_game_server::game_config::GameConfig;
+use risk_board_game_server::map::MapDefinition;
+use risk_board_game_server::validation::ValidationError;
+
+const USAGE: &str = "Usage: validate_map [--json] <file>...";
+
+struct Args {
+    json: bool,
+    files: Vec<String>,
+}
+
+// Format: cargo run --bin validate_map [--json] <file>...
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args {
+        json: false,
+        files: vec![],
+    };
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+            _ => parsed.files.push(arg),
+        }
+    }
+    if parsed.files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(parsed)
+}
+
+// Scenarios place players or reference a map, anything else is read as a map
+fn validate_file(filename: &str) -> Result<Vec<ValidationError>, String> {
+    let data = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let value: serde_json::Value = serde_json::from_str(&data).unwrap_or_default();
+    if value.get("players").is_some() || value.get("map").is_some() {
+        Ok(GameConfig::read_from_file(filename)?.validate())
+    } else {
+        Ok(MapDefinition::read_from_file(filename)?.validate())
+    }
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut failed = false;
+    let mut report = serde_json::Map::new();
+    for file in &args.files {
+        match validate_file(file) {
+            Ok(errors) => {
+                failed |= !errors.is_empty();
+                if args.json {
+                    report.insert(file.clone(), serde_json::json!(errors));
+                } else if errors.is_empty() {
+                    println!("{}: ok", file);
+                } else {
+                    println!("{}: {} problems", file, errors.len());
+                    for error in &errors {
+                        println!("  {}", error);
+                    }
+                }
+            }
+            Err(e) => {
+                failed = true;
+                if args.json {
+                    report.insert(file.clone(), serde_json::json!({ "error": e }));
+                } else {
+                    println!("{}", e);
+                }
+            }
+        }
+    }
+    if args.json {
+        println!("{}", serde_json::Value::Object(report));
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
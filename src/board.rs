@@ -1,15 +1,25 @@
 // board.rs
 use crate::continent::Continent;
+use crate::layout::MapLayout;
 use crate::player::Player;
 use crate::territory::Territory;
 use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Board {
     pub territories: HashMap<String, Territory>,
     pub continents: HashMap<String, Continent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<MapLayout>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
@@ -17,6 +27,7 @@ impl Board {
         Self {
             territories: HashMap::new(),
             continents: HashMap::new(),
+            layout: None,
         }
     }
 
@@ -36,25 +47,29 @@ impl Board {
         self.continents.get(name)
     }
 
-    pub fn shuffle_and_distribute_territories(&mut self, players: &mut Vec<Player>) {
+    pub fn shuffle_and_distribute_territories<R: Rng>(&mut self, players: &mut [Player], rng: &mut R) {
+        // Sort first so the same rng always deals the same territories
         let mut territories: Vec<&String> = self.territories.keys().collect();
-        let mut rng = rand::thread_rng();
-        territories.shuffle(&mut rng);
+        territories.sort();
+        territories.shuffle(rng);
 
-        let mut continent_territory_map: HashMap<String, Vec<&String>> = HashMap::new();
+        let mut continent_territory_map: BTreeMap<String, Vec<&String>> = BTreeMap::new();
 
         // Map territories to their continents
         for territory in &territories {
             let continent_name = &self.territories[*territory].continent;
             continent_territory_map.entry(continent_name.clone())
-                .or_insert(Vec::new())
+                .or_default()
                 .push(territory);
         }
 
+        let mut continent_groups: Vec<Vec<&String>> = continent_territory_map.into_values().collect();
+        continent_groups.shuffle(rng);
+
         // Distribute territories ensuring no player gets all territories of a continent
         let mut player_index = 0;
-        for (_, continent_territories) in &mut continent_territory_map {
-            continent_territories.shuffle(&mut rng);
+        for continent_territories in &mut continent_groups {
+            continent_territories.shuffle(rng);
 
             for territory in continent_territories {
                 players[player_index].add_territory(territory);
@@ -62,23 +77,5 @@ impl Board {
                 player_index = (player_index + 1) % players.len();
             }
         }
-
-        // Distribute remaining armies
-        let mut remaining_armies = players.len() as u16 * 5;  // Example: Each player gets 5 additional armies to distribute
-        while remaining_armies > 0 {
-            for player in players.iter_mut() {
-                if remaining_armies == 0 {
-                    break;
-                }
-                let territories: Vec<String> = player.territories.iter().cloned().collect();
-                for territory in territories {
-                    player.reinforce(&territory, 1);
-                    remaining_armies -= 1;
-                    if remaining_armies == 0 {
-                        break;
-                    }
-                }
-            }
-        }
     }
 }
@@ -0,0 +1,316 @@
+// bot.rs
+use crate::game::{Action, GameState};
+use crate::mcts::{Difficulty, MctsBot};
+use crate::player::Player;
+use crate::turn_phase::TurnPhase;
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Minimum conquer probability the greedy bot wants before it keeps attacking
+const GREEDY_ATTACK_THRESHOLD: f64 = 0.5;
+
+pub trait Bot: Send {
+    fn name(&self) -> &str;
+
+    // Pick one of state.possible_actions for the current player. The limits in the returned
+    // action (max_armies, max_dice) may be lowered to choose a smaller amount.
+    fn choose_action(&mut self, state: &GameState) -> Action;
+}
+
+pub type BotFactory = Box<dyn Fn() -> Box<dyn Bot> + Send + Sync>;
+
+pub struct RandomBot;
+
+impl Bot for RandomBot {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Action {
+        random_action(state)
+    }
+}
+
+pub struct GreedyBot;
+
+impl Bot for GreedyBot {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Action {
+        greedy_action(state)
+    }
+}
+
+pub struct BotRegistry {
+    factories: HashMap<String, BotFactory>,
+}
+
+impl Default for BotRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register("random", || Box::new(RandomBot));
+        registry.register("greedy", || Box::new(GreedyBot));
+        registry.register("mcts_easy", || Box::new(MctsBot::new(Difficulty::Easy)));
+        registry.register("mcts_medium", || Box::new(MctsBot::new(Difficulty::Medium)));
+        registry.register("mcts_hard", || Box::new(MctsBot::new(Difficulty::Hard)));
+        registry
+    }
+}
+
+impl BotRegistry {
+    // An empty registry, use default() to get one with the built-in bots
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn Bot> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn Bot>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.factories.keys().cloned().sorted().collect()
+    }
+
+    // Instantiate a bot for every bot seat, human seats are None
+    pub fn create_seats(&self, seats: &[Seat]) -> Result<Vec<Option<Box<dyn Bot>>>, String> {
+        seats
+            .iter()
+            .map(|seat| match seat {
+                Seat::Human => Ok(None),
+                Seat::Bot(name) => self
+                    .create(name)
+                    .map(Some)
+                    .ok_or(format!("Unknown bot '{}'", name)),
+            })
+            .collect()
+    }
+}
+
+// A seat is "human" or the registry name of the bot playing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Seat {
+    Human,
+    Bot(String),
+}
+
+impl From<String> for Seat {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "human" => Seat::Human,
+            _ => Seat::Bot(name),
+        }
+    }
+}
+
+impl From<Seat> for String {
+    fn from(seat: Seat) -> Self {
+        match seat {
+            Seat::Human => "human".to_string(),
+            Seat::Bot(name) => name,
+        }
+    }
+}
+
+// Ends the phase where that is allowed, otherwise takes the first possible action
+pub fn default_action(state: &GameState) -> Action {
+    state
+        .possible_actions
+        .iter()
+        .find(|action| matches!(action, Action::EndPhase))
+        .or(state.possible_actions.first())
+        .cloned()
+        .unwrap_or(Action::EndPhase)
+}
+
+pub fn random_action(state: &GameState) -> Action {
+    let mut rng = thread_rng();
+    let Some(action) = state.possible_actions.choose(&mut rng) else {
+        return default_action(state);
+    };
+    let mut action = action.clone();
+
+    match &mut action {
+        Action::Reinforce { max_armies, .. } | Action::Fortify { max_armies, .. } => {
+            *max_armies = rng.gen_range(1..=*max_armies);
+        }
+        Action::MoveArmies {
+            max_armies,
+            min_armies,
+            ..
+        } => {
+            *max_armies = rng.gen_range((*min_armies).min(*max_armies)..=*max_armies);
+        }
+        _ => {}
+    }
+    action
+}
+
+pub fn greedy_action(state: &GameState) -> Action {
+    if state.possible_actions.is_empty() {
+        return default_action(state);
+    }
+    let player = &state.players[state.current_turn];
+    let fallback = default_action(state);
+
+    match state.turn_phase {
+        TurnPhase::Reinforce => {
+            // Cards are always worth trading in before placing armies
+            if let Some(trade) = state
+                .possible_actions
+                .iter()
+                .find(|action| matches!(action, Action::TradeCards { .. }))
+            {
+                return trade.clone();
+            }
+
+            state
+                .possible_actions
+                .iter()
+                .filter_map(|action| match action {
+                    Action::Reinforce { territory, .. } => {
+                        Some((action, placement_score(state, player, territory)))
+                    }
+                    _ => None,
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(action, _)| action.clone())
+                .unwrap_or(fallback)
+        }
+        TurnPhase::Attack => state
+            .possible_actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::Attack { from, to, .. } => state
+                    .conquer_probs
+                    .iter()
+                    .find(|(f, t, _)| f == from && t == to)
+                    .map(|&(_, _, prob)| (action, prob)),
+                _ => None,
+            })
+            .filter(|&(_, prob)| prob >= GREEDY_ATTACK_THRESHOLD)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(action, _)| action.clone())
+            .unwrap_or(fallback),
+        // Push everything forward, the conquered territory is the new front line
+        TurnPhase::MoveArmies => fallback,
+        TurnPhase::Fortify => {
+            // Move the largest interior stack to the most threatened border it can reach
+            let best_source = player
+                .territories
+                .iter()
+                .filter(|territory| enemy_neighbors(state, player, territory).is_empty())
+                .filter(|territory| player.get_armies(territory) > 1)
+                .max_by_key(|territory| player.get_armies(territory));
+
+            best_source
+                .and_then(|source| {
+                    state
+                        .possible_actions
+                        .iter()
+                        .filter_map(|action| match action {
+                            Action::Fortify { from, to, .. } if from == source => {
+                                let threat = border_threat(state, player, to);
+                                (threat > 0.0).then_some((action, threat))
+                            }
+                            _ => None,
+                        })
+                        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                        .map(|(action, _)| action.clone())
+                })
+                .unwrap_or(fallback)
+        }
+        TurnPhase::GameOver => fallback,
+    }
+}
+
+// Adjacent territories owned by other players together with their army counts
+pub fn enemy_neighbors(state: &GameState, player: &Player, territory: &str) -> Vec<(String, u16)> {
+    let Some(territory) = state.board.get_territory(territory) else {
+        return vec![];
+    };
+    territory
+        .adjacent_territories
+        .iter()
+        .filter(|adjacent| !player.territories.contains(*adjacent))
+        .filter_map(|adjacent| {
+            state
+                .players
+                .iter()
+                .find(|p| p.territories.contains(adjacent))
+                .map(|owner| (adjacent.clone(), owner.get_armies(adjacent)))
+        })
+        .collect()
+}
+
+// Enemy armies next to the territory relative to the armies already defending it
+fn border_threat(state: &GameState, player: &Player, territory: &str) -> f64 {
+    let enemy_armies: u16 = enemy_neighbors(state, player, territory)
+        .iter()
+        .map(|(_, armies)| armies)
+        .sum();
+    enemy_armies as f64 / (player.get_armies(territory) as f64 + 1.0)
+}
+
+// Prefer borders that can strike the weakest neighbour, weighted by the bonus of continents
+// the player is close to completing
+fn placement_score(state: &GameState, player: &Player, territory: &str) -> f64 {
+    let Some(weakest_enemy) = enemy_neighbors(state, player, territory)
+        .iter()
+        .map(|&(_, armies)| armies)
+        .min()
+    else {
+        return f64::MIN;
+    };
+
+    let continent_score = state
+        .board
+        .get_territory(territory)
+        .and_then(|t| state.board.get_continent(&t.continent))
+        .map(|continent| {
+            let owned = continent
+                .territories
+                .iter()
+                .filter(|t| player.territories.contains(*t))
+                .count();
+            continent.bonus_armies as f64 * owned as f64 / continent.territories.len() as f64
+        })
+        .unwrap_or(0.0);
+
+    let strike_ratio = (player.get_armies(territory) + state.reinforcement_armies) as f64
+        / (weakest_enemy as f64 + 1.0);
+
+    continent_score + strike_ratio
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    // Bots can be called outside the server, which only asks when there is something to do
+    #[test]
+    fn bots_handle_no_possible_actions() {
+        let mut state = Game::new_seeded(None, Some(3), 0).get_game_state();
+        state.possible_actions.clear();
+        let registry = BotRegistry::default();
+        for name in registry.names() {
+            let mut bot = registry.create(&name).unwrap();
+            assert_eq!(bot.choose_action(&state), Action::EndPhase, "{}", name);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 use crate::board::Board;
 use crate::game::Game;
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -30,13 +30,14 @@ impl Card {
 }
 
 impl Game {
-    pub fn create_deck(board: &Board) -> Vec<Card> {
+    pub fn create_deck<R: Rng>(board: &Board, rng: &mut R) -> Vec<Card> {
         let mut deck = Vec::new();
-        let mut rng = thread_rng();
-        let mut card_types = vec![CardKind::Infantry, CardKind::Cavalry, CardKind::Artillery];
+        let mut card_types = [CardKind::Infantry, CardKind::Cavalry, CardKind::Artillery];
 
-        for territory_name in board.territories.keys() {
-            card_types.shuffle(&mut rng);
+        let mut territory_names: Vec<&String> = board.territories.keys().collect();
+        territory_names.sort();
+        for territory_name in territory_names {
+            card_types.shuffle(rng);
             deck.push(Card::new(
                 Some(territory_name.clone()),
                 card_types[0].clone(),
@@ -47,7 +48,7 @@ impl Game {
         deck.push(Card::new(None, CardKind::Joker));
         deck.push(Card::new(None, CardKind::Joker));
 
-        deck.shuffle(&mut rng);
+        deck.shuffle(rng);
         deck
     }
 
@@ -65,10 +66,8 @@ impl Game {
             return true;
         }
     
-        if joker_count > 0 {
-            if infantry_count + cavalry_count + artillery_count + joker_count == 3 {
-                return true;
-            }
+        if joker_count > 0 && infantry_count + cavalry_count + artillery_count + joker_count == 3 {
+            return true;
         }
     
         false
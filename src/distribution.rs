@@ -0,0 +1,289 @@
+// distribution.rs
+use crate::board::Board;
+use crate::player::Player;
+use itertools::Itertools;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// How the territories are dealt when a game starts without fixed positions
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributionStrategy {
+    // Round-robin through one continent after another
+    #[default]
+    Continents,
+    // Shuffled and dealt round-robin, regardless of continents
+    Random,
+    // Every territory goes to the player with the fewest in its continent, so nobody gets an
+    // outsized share of one
+    Balanced,
+    // Players pick in snake order (1, 2, 3, 3, 2, 1, 1, ...), each taking the territory that
+    // best completes their continents and connects to what they hold
+    Draft,
+    // Owners are given in assignments
+    Manual,
+}
+
+// Where the starting armies beyond the one on every territory go
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArmyPlacement {
+    // One army per territory in turn
+    #[default]
+    Even,
+    // Every army to a random territory
+    Random,
+    // Random territories, weighted by the enemy territories next to them
+    Borders,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DistributionConfig {
+    #[serde(default)]
+    pub strategy: DistributionStrategy,
+    #[serde(default)]
+    pub placement: ArmyPlacement,
+    // Territory to player id for the manual strategy, every territory has to be listed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub assignments: BTreeMap<String, usize>,
+}
+
+impl DistributionConfig {
+    // Deals the territories with one army each and places the rest of initial_armies
+    pub fn distribute<R: Rng>(
+        &self,
+        board: &mut Board,
+        players: &mut [Player],
+        initial_armies: u16,
+        rng: &mut R,
+    ) -> Result<(), String> {
+        match self.strategy {
+            DistributionStrategy::Continents => {
+                board.shuffle_and_distribute_territories(players, rng)
+            }
+            DistributionStrategy::Random => deal_random(board, players, rng),
+            DistributionStrategy::Balanced => deal_balanced(board, players, rng),
+            DistributionStrategy::Draft => deal_draft(board, players, rng),
+            DistributionStrategy::Manual => deal_manual(board, players, &self.assignments)?,
+        }
+        place_armies(board, players, initial_armies, self.placement, rng)
+    }
+}
+
+// Territory names sorted first so the same rng always deals the same way
+fn shuffled_territories<R: Rng>(board: &Board, rng: &mut R) -> Vec<String> {
+    let mut territories: Vec<String> = board.territories.keys().cloned().sorted().collect();
+    territories.shuffle(rng);
+    territories
+}
+
+fn deal_random<R: Rng>(board: &Board, players: &mut [Player], rng: &mut R) {
+    let count = players.len();
+    for (index, territory) in shuffled_territories(board, rng).iter().enumerate() {
+        players[index % count].add_territory(territory);
+        players[index % count].reinforce(territory, 1);
+    }
+}
+
+// Players with the larger quota come first in a random order, so every player ends up with
+// as many territories as in a round-robin deal
+fn quotas<R: Rng>(territories: usize, players: usize, rng: &mut R) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..players).collect();
+    order.shuffle(rng);
+    let mut quotas = vec![territories / players; players];
+    for &player in order.iter().take(territories % players) {
+        quotas[player] += 1;
+    }
+    quotas
+}
+
+fn deal_balanced<R: Rng>(board: &Board, players: &mut [Player], rng: &mut R) {
+    let mut remaining = quotas(board.territories.len(), players.len(), rng);
+    let mut by_continent: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for territory in shuffled_territories(board, rng) {
+        let continent = board.territories[&territory].continent.as_str();
+        by_continent.entry(continent).or_default().push(territory);
+    }
+
+    // Largest continents first, while every player still has room in their quota
+    let mut continents: Vec<Vec<String>> = by_continent.into_values().collect();
+    continents.shuffle(rng);
+    continents.sort_by_key(|territories| std::cmp::Reverse(territories.len()));
+    for territories in continents {
+        let mut held = vec![0; players.len()];
+        for territory in territories {
+            let mut order: Vec<usize> = (0..players.len()).collect();
+            order.shuffle(rng);
+            let player = order
+                .into_iter()
+                .filter(|&player| remaining[player] > 0)
+                .min_by_key(|&player| (held[player], players[player].territories.len()))
+                .expect("The quotas add up to the number of territories");
+            players[player].add_territory(&territory);
+            players[player].reinforce(&territory, 1);
+            held[player] += 1;
+            remaining[player] -= 1;
+        }
+    }
+}
+
+fn deal_draft<R: Rng>(board: &Board, players: &mut [Player], rng: &mut R) {
+    let mut available = shuffled_territories(board, rng);
+    let mut round = 0;
+    while !available.is_empty() {
+        let mut order: Vec<usize> = (0..players.len()).collect();
+        if round % 2 == 1 {
+            order.reverse();
+        }
+        for player in order {
+            if available.is_empty() {
+                break;
+            }
+            // The first best pick wins, the shuffle breaks ties
+            let mut best = 0;
+            let mut best_score = f64::MIN;
+            for (index, territory) in available.iter().enumerate() {
+                let score = draft_score(board, players, player, territory);
+                if score > best_score {
+                    best = index;
+                    best_score = score;
+                }
+            }
+            let territory = available.remove(best);
+            players[player].add_territory(&territory);
+            players[player].reinforce(&territory, 1);
+        }
+        round += 1;
+    }
+}
+
+// Continents are worth their bonus per territory, more so the more of them the player
+// already holds and less so the more the others hold. Neighbors add to it.
+fn draft_score(board: &Board, players: &[Player], player: usize, territory: &str) -> f64 {
+    let territory = &board.territories[territory];
+    let continent_score = board.get_continent(&territory.continent).map_or(0.0, |continent| {
+        let size = continent.territories.len().max(1) as f64;
+        let held = |p: &Player| {
+            continent
+                .territories
+                .iter()
+                .filter(|t| p.territories.contains(*t))
+                .count() as f64
+        };
+        let own = held(&players[player]);
+        let others: f64 = players
+            .iter()
+            .filter(|p| p.id != players[player].id)
+            .map(held)
+            .sum();
+        continent.bonus_armies as f64 / size * (1.0 + own) * (1.0 - others / size)
+    });
+    let own_neighbors = territory
+        .adjacent_territories
+        .iter()
+        .filter(|adjacent| players[player].territories.contains(*adjacent))
+        .count();
+    continent_score + 0.5 * own_neighbors as f64
+}
+
+fn deal_manual(
+    board: &Board,
+    players: &mut [Player],
+    assignments: &BTreeMap<String, usize>,
+) -> Result<(), String> {
+    for (territory, &player) in assignments {
+        if board.get_territory(territory).is_none() {
+            return Err(format!("Assigned territory {} is not on the map", territory));
+        }
+        let player = players.get_mut(player).ok_or(format!(
+            "{} is assigned to player {}, who doesn't exist",
+            territory, player
+        ))?;
+        player.add_territory(territory);
+        player.reinforce(territory, 1);
+    }
+    if let Some(territory) = board
+        .territories
+        .keys()
+        .sorted()
+        .find(|territory| !assignments.contains_key(*territory))
+    {
+        return Err(format!("{} is not assigned to any player", territory));
+    }
+    if let Some(player) = players.iter().find(|player| player.territories.is_empty()) {
+        return Err(format!("{} is not assigned any territory", player.name));
+    }
+    Ok(())
+}
+
+// Tops every player up to initial_armies
+fn place_armies<R: Rng>(
+    board: &Board,
+    players: &mut [Player],
+    initial_armies: u16,
+    placement: ArmyPlacement,
+    rng: &mut R,
+) -> Result<(), String> {
+    let owners: HashMap<String, usize> = players
+        .iter()
+        .flat_map(|player| player.territories.iter().map(|t| (t.clone(), player.id)))
+        .collect();
+
+    for player in players.iter_mut() {
+        let on_board: u16 = player.armies.values().sum();
+        let mut remaining = initial_armies.checked_sub(on_board).ok_or(format!(
+            "{} was dealt {} armies but starts with only {}",
+            player.name, on_board, initial_armies
+        ))?;
+        let territories: Vec<String> = player.territories.iter().cloned().sorted().collect();
+        if territories.is_empty() {
+            continue;
+        }
+
+        // Border weights, territories surrounded by their own side get none
+        let weights: Vec<usize> = territories
+            .iter()
+            .map(|territory| {
+                board.territories[territory]
+                    .adjacent_territories
+                    .iter()
+                    .filter(|adjacent| owners.get(*adjacent) != Some(&player.id))
+                    .count()
+            })
+            .collect();
+        let placement = match placement {
+            ArmyPlacement::Borders if weights.iter().all(|&w| w == 0) => ArmyPlacement::Even,
+            placement => placement,
+        };
+
+        match placement {
+            ArmyPlacement::Even => {
+                while remaining > 0 {
+                    let mut order = territories.clone();
+                    order.shuffle(rng);
+                    for territory in order.iter().take(remaining as usize) {
+                        player.reinforce(territory, 1);
+                    }
+                    remaining = remaining.saturating_sub(order.len() as u16);
+                }
+            }
+            ArmyPlacement::Random => {
+                for _ in 0..remaining {
+                    let territory = territories.choose(rng).expect("Players hold a territory");
+                    player.reinforce(territory, 1);
+                }
+            }
+            ArmyPlacement::Borders => {
+                let index = WeightedIndex::new(&weights).expect("Some weight is positive");
+                for _ in 0..remaining {
+                    player.reinforce(&territories[index.sample(rng)], 1);
+                }
+            }
+        }
+    }
+    Ok(())
+}
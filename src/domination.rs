@@ -0,0 +1,316 @@
+// domination.rs
+use crate::layout::{MapLayout, TerritoryLayout};
+use crate::map::{ContinentConfig, MapDefinition, TerritoryConfig};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+// Conversion from and to the Domination / Conquer Club .map text format:
+//
+//   name Some Map
+//   [files]
+//   pic some_pic.png              background image, prv is the preview image
+//   [continents]
+//   North_America 5 yellow        name, bonus armies, color
+//   [countries]
+//   1 Alaska 1 45 48              index, name, continent index, x, y
+//   [borders]
+//   1 2 4 30                      country index followed by the countries it borders
+//
+// Indices are 1-based and follow the order of the section. Names can't contain spaces,
+// they are written with underscores instead, so names with underscores can't be exported. Lines starting with ';' are comments and
+// other sections are ignored. Country coordinates become the territory centers.
+
+// Colors given to continents on export, the format requires one
+const COLORS: [&str; 8] = [
+    "yellow", "green", "blue", "red", "orange", "magenta", "cyan", "gray",
+];
+
+enum Section {
+    None,
+    Files,
+    Continents,
+    Countries,
+    Borders,
+    Other,
+}
+
+impl MapDefinition {
+    // default_name is used when the file has no name line, e.g. the file stem
+    pub fn from_domination(text: &str, default_name: &str) -> Result<Self, String> {
+        let mut name = default_name.to_string();
+        let mut continents: Vec<ContinentConfig> = vec![];
+        let mut territories: Vec<TerritoryConfig> = vec![];
+        // Resolved once all countries are known, kept with their line number
+        let mut borders: Vec<(usize, usize, Vec<usize>)> = vec![];
+        let mut layout = MapLayout::default();
+
+        let mut section = Section::None;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let error = |message: &str| format!("Line {}: {}", number + 1, message);
+            if line.starts_with('[') {
+                section = match line.to_lowercase().as_str() {
+                    "[files]" => Section::Files,
+                    "[continents]" => Section::Continents,
+                    "[countries]" => Section::Countries,
+                    "[borders]" => Section::Borders,
+                    _ => Section::Other,
+                };
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match section {
+                Section::None => {
+                    if let Some(value) = line.strip_prefix("name ") {
+                        name = value.trim().to_string();
+                    }
+                }
+                Section::Files => match fields[..] {
+                    ["pic", image] => layout.background_image = Some(image.to_string()),
+                    ["prv", image] => layout.thumbnail_image = Some(image.to_string()),
+                    _ => {}
+                },
+                Section::Continents => {
+                    if fields.len() < 2 {
+                        return Err(error("Expected <name> <bonus> [color]"));
+                    }
+                    let bonus_armies = fields[1]
+                        .parse()
+                        .map_err(|_| error(&format!("Invalid bonus {}", fields[1])))?;
+                    continents.push(ContinentConfig {
+                        name: decode_name(fields[0]),
+                        bonus_armies,
+                        territories: vec![],
+                    });
+                }
+                Section::Countries => {
+                    if fields.len() < 3 {
+                        return Err(error("Expected <index> <name> <continent> [x y]"));
+                    }
+                    let index = parse_index(fields[0]).map_err(|e| error(&e))?;
+                    if index != territories.len() + 1 {
+                        return Err(error(&format!(
+                            "Country {} is out of order, expected {}",
+                            index,
+                            territories.len() + 1
+                        )));
+                    }
+                    let continent = parse_index(fields[2]).map_err(|e| error(&e))?;
+                    let continent = continents
+                        .get_mut(continent - 1)
+                        .ok_or(error(&format!("Unknown continent {}", continent)))?;
+                    let territory_name = decode_name(fields[1]);
+                    continent.territories.push(territory_name.clone());
+                    // Exported maps without a layout have 0 0, which is no position
+                    let territory_layout = match (fields.get(3), fields.get(4)) {
+                        (Some(x), Some(y)) => {
+                            let x: f64 = x.parse().map_err(|_| error(&format!("Invalid x {}", x)))?;
+                            let y: f64 = y.parse().map_err(|_| error(&format!("Invalid y {}", y)))?;
+                            (x != 0.0 || y != 0.0).then(|| TerritoryLayout::at(x, y))
+                        }
+                        _ => None,
+                    };
+                    territories.push(TerritoryConfig {
+                        name: territory_name,
+                        continent: continent.name.clone(),
+                        adjacent_territories: vec![],
+                        layout: territory_layout,
+                    });
+                }
+                Section::Borders => {
+                    let indices = fields
+                        .iter()
+                        .map(|field| parse_index(field))
+                        .collect::<Result<Vec<usize>, String>>()
+                        .map_err(|e| error(&e))?;
+                    borders.push((number + 1, indices[0], indices[1..].to_vec()));
+                }
+                Section::Other => {}
+            }
+        }
+
+        if territories.is_empty() {
+            return Err("No [countries] found".to_string());
+        }
+        for (line, country, adjacent) in borders {
+            let names = adjacent
+                .iter()
+                .map(|&index| {
+                    territories
+                        .get(index - 1)
+                        .map(|t| t.name.clone())
+                        .ok_or(format!(
+                            "Line {}: Border to unknown country {}",
+                            line, index
+                        ))
+                })
+                .collect::<Result<Vec<String>, String>>()?;
+            territories
+                .get_mut(country - 1)
+                .ok_or(format!(
+                    "Line {}: Borders for unknown country {}",
+                    line, country
+                ))?
+                .adjacent_territories
+                .extend(names);
+        }
+
+        Ok(MapDefinition {
+            name,
+            territories,
+            continents,
+            starting_armies: BTreeMap::new(),
+            layout: (layout != MapLayout::default()).then_some(layout),
+        })
+    }
+
+    // Every territory must belong to a known continent and only border known territories
+    pub fn to_domination(&self) -> Result<String, String> {
+        let continent_index: HashMap<&str, usize> = self
+            .continents
+            .iter()
+            .enumerate()
+            .map(|(index, c)| (c.name.as_str(), index + 1))
+            .collect();
+        let territory_index: HashMap<&str, usize> = self
+            .territories
+            .iter()
+            .enumerate()
+            .map(|(index, t)| (t.name.as_str(), index + 1))
+            .collect();
+
+        let mut text = String::new();
+        let _ = writeln!(text, "name {}", self.name);
+        if let Some(layout) = &self.layout {
+            if layout.background_image.is_some() || layout.thumbnail_image.is_some() {
+                let _ = writeln!(text, "\n[files]");
+            }
+            if let Some(image) = &layout.background_image {
+                let _ = writeln!(text, "pic {}", image);
+            }
+            if let Some(image) = &layout.thumbnail_image {
+                let _ = writeln!(text, "prv {}", image);
+            }
+        }
+        let _ = writeln!(text, "\n[continents]");
+        for (index, continent) in self.continents.iter().enumerate() {
+            let _ = writeln!(
+                text,
+                "{} {} {}",
+                encode_name(&continent.name)?,
+                continent.bonus_armies,
+                COLORS[index % COLORS.len()]
+            );
+        }
+
+        let _ = writeln!(text, "\n[countries]");
+        for (index, territory) in self.territories.iter().enumerate() {
+            let continent = continent_index
+                .get(territory.continent.as_str())
+                .ok_or(format!(
+                    "{} belongs to unknown continent {}",
+                    territory.name, territory.continent
+                ))?;
+            let center = territory.layout.as_ref().and_then(|layout| layout.center);
+            let (x, y) = center.map_or((0.0, 0.0), |center| (center.x, center.y));
+            let _ = writeln!(
+                text,
+                "{} {} {} {:.0} {:.0}",
+                index + 1,
+                encode_name(&territory.name)?,
+                continent,
+                x,
+                y
+            );
+        }
+
+        let _ = writeln!(text, "\n[borders]");
+        for (index, territory) in self.territories.iter().enumerate() {
+            let mut line = (index + 1).to_string();
+            for adjacent in &territory.adjacent_territories {
+                let adjacent_index = territory_index.get(adjacent.as_str()).ok_or(format!(
+                    "{} borders unknown territory {}",
+                    territory.name, adjacent
+                ))?;
+                let _ = write!(line, " {}", adjacent_index);
+            }
+            let _ = writeln!(text, "{}", line);
+        }
+        Ok(text)
+    }
+}
+
+fn parse_index(field: &str) -> Result<usize, String> {
+    match field.parse::<usize>() {
+        Ok(index) if index > 0 => Ok(index),
+        _ => Err(format!("Invalid index {}", field)),
+    }
+}
+
+fn decode_name(name: &str) -> String {
+    name.replace('_', " ")
+}
+
+// Underscores already stand for spaces and other whitespace separates the fields, both
+// would come back as a different name
+fn encode_name(name: &str) -> Result<String, String> {
+    if name.contains('_') || name.chars().any(|c| c.is_whitespace() && c != ' ') {
+        return Err(format!(
+            "{} can't be written in the .map format, names can't contain underscores or tabs",
+            name
+        ));
+    }
+    Ok(name.replace(' ', "_"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Territory with its continent and borders
+    type TerritoryEntry = (String, String, Vec<String>);
+
+    // Sorted territories, and continents with their bonus
+    fn board(map: &MapDefinition) -> (Vec<TerritoryEntry>, Vec<(String, u16)>) {
+        let mut territories: Vec<_> = map
+            .territories
+            .iter()
+            .map(|t| {
+                let mut adjacent = t.adjacent_territories.clone();
+                adjacent.sort();
+                (t.name.clone(), t.continent.clone(), adjacent)
+            })
+            .collect();
+        territories.sort();
+        let mut continents: Vec<_> = map
+            .continents
+            .iter()
+            .map(|c| (c.name.clone(), c.bonus_armies))
+            .collect();
+        continents.sort();
+        (territories, continents)
+    }
+
+    #[test]
+    fn builtin_maps_round_trip() {
+        for name in MapDefinition::builtin_names() {
+            let map = MapDefinition::builtin(name).unwrap();
+            let text = map.to_domination().unwrap();
+            let imported = MapDefinition::from_domination(&text, name).unwrap();
+            assert_eq!(imported.name, map.name, "{}", name);
+            assert_eq!(board(&imported), board(&map), "{}", name);
+            assert_eq!(imported.validate(), vec![], "{}", name);
+        }
+    }
+
+    #[test]
+    fn underscores_are_not_exported() {
+        let mut map = MapDefinition::builtin("tiny").unwrap();
+        map.continents[0].name = "North_East".to_string();
+        assert!(map.to_domination().is_err());
+    }
+}
@@ -0,0 +1,229 @@
+// engine.rs
+use crate::bot::{default_action, Bot, BotRegistry};
+use crate::game::{Action, GameState};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long an engine has to exit after {"type":"quit"} before it is killed
+const QUIT_GRACE: Duration = Duration::from_millis(100);
+
+// External engines are local programs speaking line-delimited JSON over stdin/stdout.
+// On every turn the server writes one request line:
+//   {"type":"choose_action","id":7,"player_id":0,"game_state":{...},"possible_actions":[...]}
+// and the engine answers with one line echoing the id:
+//   {"id":7,"action":{"Attack":{"from":"Siam","to":"Indonesia","max_dice":3}}}
+// The action must be one of possible_actions, the limits (max_armies, max_dice) may be lowered.
+// Invalid or late answers are replaced by a default action. {"type":"quit"} is sent on shutdown.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+impl EngineConfig {
+    pub fn load_from_file(filename: &str) -> Result<Vec<Self>, std::io::Error> {
+        let data = std::fs::read_to_string(filename)?;
+        let engines: Vec<EngineConfig> = serde_json::from_str(&data)?;
+        Ok(engines)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EngineRequest<'a> {
+    ChooseAction {
+        id: u64,
+        player_id: usize,
+        game_state: &'a GameState,
+        possible_actions: &'a [Action],
+    },
+    Quit,
+}
+
+#[derive(Deserialize)]
+struct EngineResponse {
+    id: u64,
+    action: Action,
+}
+
+struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: mpsc::Receiver<String>,
+}
+
+pub struct EngineBot {
+    config: EngineConfig,
+    process: Option<EngineProcess>,
+    next_id: u64,
+}
+
+impl EngineBot {
+    // The subprocess is started on the first request, so creating a seat never fails
+    pub fn new(config: EngineConfig) -> Self {
+        Self {
+            config,
+            process: None,
+            next_id: 0,
+        }
+    }
+
+    fn spawn(&self) -> Result<EngineProcess, String> {
+        let mut child = Command::new(&self.config.command)
+            .args(&self.config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("Failed to start engine '{}': {}", self.config.name, e))?;
+
+        let stdin = child.stdin.take().ok_or("Engine stdin is not available")?;
+        let stdout = child.stdout.take().ok_or("Engine stdout is not available")?;
+
+        // Read on a separate thread so a silent engine can be timed out
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(EngineProcess {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn request_action(&mut self, state: &GameState) -> Result<Action, String> {
+        if self.process.is_none() {
+            self.process = Some(self.spawn()?);
+        }
+        let process = self.process.as_mut().expect("Engine process was just started");
+
+        self.next_id += 1;
+        let id = self.next_id;
+        let request = EngineRequest::ChooseAction {
+            id,
+            player_id: state.current_turn,
+            game_state: state,
+            possible_actions: &state.possible_actions,
+        };
+        let line = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+        if let Err(e) = writeln!(process.stdin, "{}", line).and_then(|_| process.stdin.flush()) {
+            // The engine is gone, try a fresh process next turn
+            self.process = None;
+            return Err(format!("Failed to write to engine: {}", e));
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(self.config.timeout_ms);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let line = match process.lines.recv_timeout(remaining) {
+                Ok(line) => line,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(format!("No answer within {} ms", self.config.timeout_ms))
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    self.process = None;
+                    return Err("Engine closed its output".to_string());
+                }
+            };
+
+            // Answers to earlier requests that timed out are skipped
+            let response: EngineResponse = serde_json::from_str(&line)
+                .map_err(|e| format!("Invalid answer '{}': {}", line, e))?;
+            if response.id != id {
+                continue;
+            }
+
+            return if state
+                .possible_actions
+                .iter()
+                .any(|allowed| allowed.allows(&response.action))
+            {
+                Ok(response.action)
+            } else {
+                Err(format!("Action {:?} is not possible", response.action))
+            };
+        }
+    }
+}
+
+impl Bot for EngineBot {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Action {
+        match self.request_action(state) {
+            Ok(action) => action,
+            Err(e) => {
+                eprintln!("Engine '{}': {}, using default action", self.config.name, e);
+                default_action(state)
+            }
+        }
+    }
+}
+
+// Every way of losing a process ends here: it gets {"type":"quit"} and a moment to exit on its
+// own, then it is killed. Waiting on it keeps it from becoming a zombie.
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        if let Ok(line) = serde_json::to_string(&EngineRequest::Quit) {
+            let _ = writeln!(self.stdin, "{}", line).and_then(|_| self.stdin.flush());
+        }
+        let deadline = Instant::now() + QUIT_GRACE;
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+                _ => return,
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Engines registered by the server and the tools, read from the working directory
+pub const ENGINES_FILE: &str = "engines.json";
+
+impl BotRegistry {
+    // The built-in bots plus the engines listed in the file, when it exists. A file that can't
+    // be read is reported and the built-in bots remain.
+    pub fn with_engines_file(path: &str) -> Self {
+        let mut registry = Self::default();
+        if std::path::Path::new(path).exists() {
+            match EngineConfig::load_from_file(path) {
+                Ok(engines) => registry.register_engines(&engines),
+                Err(e) => eprintln!("Failed to load {}: {}", path, e),
+            }
+        }
+        registry
+    }
+
+    pub fn register_engines(&mut self, engines: &[EngineConfig]) {
+        for engine in engines {
+            let config = engine.clone();
+            self.register(&engine.name, move || Box::new(EngineBot::new(config.clone())));
+        }
+    }
+}
@@ -0,0 +1,417 @@
+// environment.rs
+use crate::bot::{default_action, Bot, BotRegistry, Seat};
+use crate::game::{Action, Game};
+use crate::turn_phase::TurnPhase;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Opponent actions played between two agent steps before the episode is cut off
+const MAX_OPPONENT_ACTIONS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardKind {
+    // +1 when the agent wins, -1 when it is defeated or another player wins, 0 otherwise
+    WinLoss,
+    // Change in the agent's share of the board since the previous step
+    TerritoryDelta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvConfig {
+    pub agent_id: usize,
+    // One bot name per opponent seat, in seat order with the agent left out
+    pub opponents: Vec<String>,
+    pub reward: RewardKind,
+    // Episodes still running after this many rounds end without a winner
+    pub max_rounds: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub action_mask: Vec<bool>,
+    pub reward: f64,
+    pub done: bool,
+}
+
+// Fixed action table, so an agent can work with plain indices:
+//   0                  EndPhase
+//   1 .. 1+N           Reinforce territory with one army
+//   1+N                TradeCards, the first possible trade
+//   2+N, 3+N           MoveArmies after a conquest, minimum and maximum
+//   4+N .. 4+N+E       Attack along each directed adjacency with as many dice as possible
+//   4+N+E .. +N*N      Fortify from/to with as many armies as possible
+// Territories are numbered in sorted order.
+struct ActionSpace {
+    territories: Vec<String>,
+    territory_index: HashMap<String, usize>,
+    edges: Vec<(usize, usize)>,
+    edge_index: HashMap<(usize, usize), usize>,
+}
+
+impl ActionSpace {
+    fn new(game: &Game) -> Self {
+        let mut territories: Vec<String> = game.board.territories.keys().cloned().collect();
+        territories.sort();
+        let territory_index: HashMap<String, usize> = territories
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), index))
+            .collect();
+
+        let mut edges = vec![];
+        for (from, name) in territories.iter().enumerate() {
+            let mut adjacent: Vec<usize> = game.board.territories[name]
+                .adjacent_territories
+                .iter()
+                .filter_map(|t| territory_index.get(t).copied())
+                .collect();
+            adjacent.sort();
+            edges.extend(adjacent.into_iter().map(|to| (from, to)));
+        }
+        let edge_index = edges
+            .iter()
+            .enumerate()
+            .map(|(index, &edge)| (edge, index))
+            .collect();
+
+        Self {
+            territories,
+            territory_index,
+            edges,
+            edge_index,
+        }
+    }
+
+    fn reinforce_offset(&self) -> usize {
+        1
+    }
+
+    fn trade_offset(&self) -> usize {
+        self.reinforce_offset() + self.territories.len()
+    }
+
+    fn move_offset(&self) -> usize {
+        self.trade_offset() + 1
+    }
+
+    fn attack_offset(&self) -> usize {
+        self.move_offset() + 2
+    }
+
+    fn fortify_offset(&self) -> usize {
+        self.attack_offset() + self.edges.len()
+    }
+
+    fn size(&self) -> usize {
+        self.fortify_offset() + self.territories.len() * self.territories.len()
+    }
+
+    // Slots a possible action makes available
+    fn slots(&self, action: &Action) -> Vec<usize> {
+        let index = |name: &String| self.territory_index[name];
+        match action {
+            Action::EndPhase => vec![0],
+            Action::Reinforce { territory, .. } => vec![self.reinforce_offset() + index(territory)],
+            Action::TradeCards { .. } => vec![self.trade_offset()],
+            Action::MoveArmies { .. } => vec![self.move_offset(), self.move_offset() + 1],
+            Action::Attack { from, to, .. } => self
+                .edge_index
+                .get(&(index(from), index(to)))
+                .map(|edge| vec![self.attack_offset() + edge])
+                .unwrap_or_default(),
+            Action::Fortify { from, to, .. } => {
+                vec![self.fortify_offset() + index(from) * self.territories.len() + index(to)]
+            }
+        }
+    }
+
+    // Turn a slot into a concrete action, or None if no possible action fills it
+    fn resolve(&self, slot: usize, possible_actions: &[Action]) -> Option<Action> {
+        let possible = possible_actions
+            .iter()
+            .find(|action| self.slots(action).contains(&slot))?;
+        let action = match possible {
+            Action::Reinforce { territory, .. } => Action::Reinforce {
+                territory: territory.clone(),
+                max_armies: 1,
+            },
+            Action::MoveArmies {
+                from,
+                to,
+                max_armies,
+                min_armies,
+            } => Action::MoveArmies {
+                from: from.clone(),
+                to: to.clone(),
+                max_armies: if slot == self.move_offset() {
+                    *min_armies
+                } else {
+                    *max_armies
+                },
+                min_armies: *min_armies,
+            },
+            other => other.clone(),
+        };
+        Some(action)
+    }
+}
+
+// Gym-style environment: the agent plays one seat and the registry bots play the others.
+// Opponents move inside step, so every observation is taken on the agent's turn.
+pub struct RiskEnv {
+    config: EnvConfig,
+    seats: Vec<Option<Box<dyn Bot>>>,
+    game: Game,
+    actions: ActionSpace,
+    done: bool,
+}
+
+impl RiskEnv {
+    pub fn new(config: EnvConfig, registry: &BotRegistry) -> Result<Self, String> {
+        let num_players = config.opponents.len() + 1;
+        if config.agent_id >= num_players {
+            return Err(format!(
+                "Agent id {} is out of range for {} players",
+                config.agent_id, num_players
+            ));
+        }
+
+        let mut seats: Vec<Seat> = config
+            .opponents
+            .iter()
+            .map(|name| Seat::Bot(name.clone()))
+            .collect();
+        seats.insert(config.agent_id, Seat::Human);
+        let seats = registry.create_seats(&seats)?;
+
+        let game = Game::try_new(None, Some(num_players))?;
+        let actions = ActionSpace::new(&game);
+        Ok(Self {
+            config,
+            seats,
+            game,
+            actions,
+            done: true,
+        })
+    }
+
+    pub fn action_count(&self) -> usize {
+        self.actions.size()
+    }
+
+    pub fn observation_size(&self) -> usize {
+        let num_players = self.game.players.len();
+        self.actions.territories.len() * (num_players + 1) + num_players + 5 + 2
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // Start a new episode. The same seed deals the same board, cards and dice, opponent
+    // bots still make their own random choices.
+    pub fn reset(&mut self, seed: u64) -> Step {
+        let num_players = self.seats.len();
+        self.game = Game::new_seeded(None, Some(num_players), seed);
+        self.actions = ActionSpace::new(&self.game);
+        self.done = false;
+        self.play_opponents();
+        self.done = self.is_over();
+        self.step_result(0.0)
+    }
+
+    pub fn step(&mut self, action_index: usize) -> Result<Step, String> {
+        if self.done {
+            return Err("Episode is over, call reset first".to_string());
+        }
+
+        let possible_actions = self.game.get_possible_actions();
+        let action = self
+            .actions
+            .resolve(action_index, &possible_actions)
+            .ok_or(format!("Action {} is not possible", action_index))?;
+
+        let territories_before = self.territory_share();
+        self.game.apply_action(self.config.agent_id, &action)?;
+        self.play_opponents();
+        self.done = self.is_over();
+
+        let reward = match self.config.reward {
+            RewardKind::WinLoss => self.outcome(),
+            RewardKind::TerritoryDelta => self.territory_share() - territories_before,
+        };
+        Ok(self.step_result(reward))
+    }
+
+    pub fn action_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; self.actions.size()];
+        if self.done {
+            return mask;
+        }
+        for action in self.game.get_possible_actions() {
+            for slot in self.actions.slots(&action) {
+                mask[slot] = true;
+            }
+        }
+        mask
+    }
+
+    // Per territory in sorted order: owner one-hot (the agent first) and armies.
+    // Then the current player one-hot, the phase one-hot, armies left to place and
+    // the agent's hand size.
+    pub fn observation(&self) -> Vec<f32> {
+        let num_players = self.game.players.len();
+        let relative = |player_id: usize| {
+            (player_id + num_players - self.config.agent_id) % num_players
+        };
+
+        let mut observation = Vec::with_capacity(self.observation_size());
+        for territory in &self.actions.territories {
+            let mut owner = vec![0.0; num_players];
+            let mut armies = 0.0;
+            if let Some(player) = self
+                .game
+                .players
+                .iter()
+                .find(|p| p.territories.contains(territory))
+            {
+                owner[relative(player.id)] = 1.0;
+                armies = player.get_armies(territory) as f32;
+            }
+            observation.extend(owner);
+            observation.push(armies);
+        }
+
+        let mut current = vec![0.0; num_players];
+        current[relative(self.game.current_turn)] = 1.0;
+        observation.extend(current);
+
+        let phase = match self.game.turn_phase {
+            TurnPhase::Reinforce => 0,
+            TurnPhase::Attack => 1,
+            TurnPhase::Fortify => 2,
+            TurnPhase::MoveArmies => 3,
+            TurnPhase::GameOver => 4,
+        };
+        let mut phases = vec![0.0; 5];
+        phases[phase] = 1.0;
+        observation.extend(phases);
+
+        observation.push(self.game.reinforcement_armies as f32);
+        observation.push(self.game.players[self.config.agent_id].cards.len() as f32);
+        observation
+    }
+
+    fn step_result(&self, reward: f64) -> Step {
+        Step {
+            observation: self.observation(),
+            action_mask: self.action_mask(),
+            reward,
+            done: self.done,
+        }
+    }
+
+    fn play_opponents(&mut self) {
+        for _ in 0..MAX_OPPONENT_ACTIONS {
+            if self.is_done() {
+                return;
+            }
+
+            let player_id = self.game.current_turn;
+            let Some(Some(bot)) = self.seats.get_mut(player_id) else {
+                return;
+            };
+            let state = self.game.get_game_state();
+            if state.possible_actions.is_empty() {
+                return;
+            }
+
+            let action = bot.choose_action(&state);
+            if let Err(e) = self.game.apply_action(player_id, &action) {
+                eprintln!(
+                    "Bot '{}' for player {} failed to apply {:?}: {}",
+                    bot.name(),
+                    player_id,
+                    action,
+                    e
+                );
+                if self
+                    .game
+                    .apply_action(player_id, &default_action(&state))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    }
+
+    // Also ends the episode when the opponents stopped before it was the agent's turn again,
+    // because they ran out of actions or got stuck, so every step is taken on the agent's turn
+    fn is_over(&self) -> bool {
+        self.is_done() || self.game.current_turn != self.config.agent_id
+    }
+
+    fn is_done(&self) -> bool {
+        self.game.turn_phase == TurnPhase::GameOver
+            || self.game.defeated_players.contains(&self.config.agent_id)
+            || self.game.round >= self.config.max_rounds
+    }
+
+    fn outcome(&self) -> f64 {
+        if self.game.defeated_players.contains(&self.config.agent_id) {
+            -1.0
+        } else if self.game.turn_phase == TurnPhase::GameOver {
+            if self.game.current_turn == self.config.agent_id {
+                1.0
+            } else {
+                -1.0
+            }
+        } else {
+            0.0
+        }
+    }
+
+    fn territory_share(&self) -> f64 {
+        self.game.players[self.config.agent_id].territories.len() as f64
+            / self.actions.territories.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::seq::IteratorRandom;
+    use rand::SeedableRng;
+
+    // Random agent against random bots, every observation has to be taken on the agent's turn
+    #[test]
+    fn steps_are_on_the_agents_turn() {
+        let config = EnvConfig {
+            agent_id: 1,
+            opponents: vec!["random".to_string(), "random".to_string()],
+            reward: RewardKind::WinLoss,
+            max_rounds: 30,
+        };
+        let mut env = RiskEnv::new(config, &BotRegistry::default()).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for seed in 0..3 {
+            let mut step = env.reset(seed);
+            while !step.done {
+                assert_eq!(env.game().current_turn, 1);
+                assert_eq!(step.observation.len(), env.observation_size());
+                assert_eq!(step.action_mask.len(), env.action_count());
+                let action = (0..step.action_mask.len())
+                    .filter(|&index| step.action_mask[index])
+                    .choose(&mut rng)
+                    .expect("The agent has a possible action");
+                step = env.step(action).unwrap();
+            }
+            assert_eq!(step.observation.len(), env.observation_size());
+            assert!(env.step(0).is_err());
+        }
+    }
+}
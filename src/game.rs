@@ -1,15 +1,18 @@
+use crate::battle;
 use crate::board::Board;
 use crate::card::Card;
-use crate::game_config::GameConfig;
+use crate::distribution::DistributionConfig;
+use crate::game_config::{CardConfig, GameConfig, StateConfig};
+use crate::map::MapDefinition;
 use crate::player::Player;
+use crate::probability_table::ProbabilityTable;
 use crate::turn_phase::TurnPhase;
 use itertools::Itertools;
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashSet, HashMap};
-use std::fs::File;
-use std::io::Read;
-use bincode;
+use std::sync::Arc;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GameState {
@@ -45,10 +48,15 @@ pub struct Game {
     pub last_attack_dice: Option<u16>,
     pub active_players: Vec<usize>,
     pub conquer_probs: Vec<(String, String, f64)>,
+    // Pairs outside prob_table, computed when they first come up
     prob_cache: HashMap<(u16, u16), f64>,
+    #[serde(skip, default = "ProbabilityTable::embedded")]
+    prob_table: Arc<ProbabilityTable>,
+    #[serde(skip, default = "StdRng::from_entropy")]
+    rng: StdRng,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum Action {
     Reinforce {
         territory: String,
@@ -76,24 +84,133 @@ pub enum Action {
     EndPhase,
 }
 
-#[derive(Serialize, Deserialize)]
-struct ProbabilityCache {
-    cache: HashMap<(u16, u16), f64>,
+impl Action {
+    // Whether a chosen action fits this possible action, with amounts up to its limits
+    pub fn allows(&self, choice: &Action) -> bool {
+        match (self, choice) {
+            (
+                Action::Reinforce {
+                    territory,
+                    max_armies,
+                },
+                Action::Reinforce {
+                    territory: chosen_territory,
+                    max_armies: chosen_armies,
+                },
+            ) => territory == chosen_territory && (1..=*max_armies).contains(chosen_armies),
+            (
+                Action::Attack { from, to, max_dice },
+                Action::Attack {
+                    from: chosen_from,
+                    to: chosen_to,
+                    max_dice: chosen_dice,
+                },
+            ) => from == chosen_from && to == chosen_to && (1..=*max_dice).contains(chosen_dice),
+            (
+                Action::Fortify {
+                    from,
+                    to,
+                    max_armies,
+                },
+                Action::Fortify {
+                    from: chosen_from,
+                    to: chosen_to,
+                    max_armies: chosen_armies,
+                },
+            ) => {
+                from == chosen_from && to == chosen_to && (1..=*max_armies).contains(chosen_armies)
+            }
+            (
+                Action::MoveArmies {
+                    from,
+                    to,
+                    max_armies,
+                    min_armies,
+                },
+                Action::MoveArmies {
+                    from: chosen_from,
+                    to: chosen_to,
+                    max_armies: chosen_armies,
+                    ..
+                },
+            ) => {
+                from == chosen_from
+                    && to == chosen_to
+                    && (*min_armies..=*max_armies).contains(chosen_armies)
+            }
+            _ => self == choice,
+        }
+    }
 }
 
 impl Game {
+    // Panics when the player count doesn't work on the map, see try_new
     pub fn new(config: Option<GameConfig>, num_players: Option<usize>) -> Self {
+        Game::try_new(config, num_players).unwrap_or_else(|e| panic!("Unable to start game: {}", e))
+    }
+
+    // Same as new, but territories, cards and dice all come from the seeded rng so a game
+    // can be replayed exactly
+    pub fn new_seeded(config: Option<GameConfig>, num_players: Option<usize>, seed: u64) -> Self {
+        Game::try_new_seeded(config, num_players, seed)
+            .unwrap_or_else(|e| panic!("Unable to start game: {}", e))
+    }
+
+    pub fn try_new(config: Option<GameConfig>, num_players: Option<usize>) -> Result<Self, String> {
+        Game::with_rng(config, num_players, StdRng::from_entropy())
+    }
+
+    pub fn try_new_seeded(
+        config: Option<GameConfig>,
+        num_players: Option<usize>,
+        seed: u64,
+    ) -> Result<Self, String> {
+        Game::with_rng(config, num_players, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(
+        config: Option<GameConfig>,
+        num_players: Option<usize>,
+        mut rng: StdRng,
+    ) -> Result<Self, String> {
+        // A state only applies to fixed starting positions
+        let state = config
+            .as_ref()
+            .filter(|cfg| !cfg.players.is_empty())
+            .and_then(|cfg| cfg.state.clone());
         let (board, players) = match config {
-            Some(cfg) => cfg.to_board_and_players(),
-            None => {
+            Some(cfg) if !cfg.players.is_empty() => cfg.to_board_and_players()?,
+            cfg => {
+                let (map, distribution) = match cfg {
+                    Some(cfg) => (cfg.map_definition()?, cfg.distribution.unwrap_or_default()),
+                    None => (MapDefinition::classic(), DistributionConfig::default()),
+                };
                 let num_players = num_players.unwrap_or(6);
-                let mut board = Game::create_board_from_config();
-                let players = Game::create_random_players(num_players, &mut board);
+                let initial_armies = map.starting_armies_for(num_players)?;
+                let mut board = map.to_board();
+                let players = Game::create_random_players(
+                    num_players,
+                    initial_armies,
+                    &distribution,
+                    &mut board,
+                    &mut rng,
+                )?;
                 (board, players)
             }
         };
 
-        let deck = Game::create_deck(&board);
+        let deck = match &state {
+            None => Game::create_deck(&board, &mut rng),
+            Some(StateConfig { deck: Some(deck), .. }) => {
+                deck.iter().map(CardConfig::to_card).collect()
+            }
+            Some(state) => {
+                let discarded: Vec<Card> =
+                    state.discard_pile.iter().map(CardConfig::to_card).collect();
+                let held = players.iter().flat_map(|p| p.cards.iter()).chain(&discarded);
+                Game::remaining_deck(&board, held, &mut rng)
+            }
+        };
         let active_players = (0..players.len()).collect();
 
         let mut game = Self {
@@ -113,79 +230,124 @@ impl Game {
             last_attack_dice: None,
             active_players,
             prob_cache: HashMap::new(),
+            prob_table: ProbabilityTable::embedded(),
             conquer_probs: vec![],
+            rng,
         };
 
-        game.load_conquer_probabilities("conquer_probabilities.bin");
-
-        game.start_turn();
-        game.initial_reinforcement_armies = game.reinforcement_armies;
-        game
-    }
-
-    fn create_board_from_config() -> Board {
-        let config_data = include_str!("config.json");
-        
-        let config: GameConfig =
-            serde_json::from_str(&config_data).expect("Unable to parse config file");
-    
-        let (board, _) = config.to_board_and_players();
-        board
+        match state {
+            Some(state) => game.restore_state(state),
+            None => {
+                game.start_turn();
+                game.initial_reinforcement_armies = game.reinforcement_armies;
+            }
+        }
+        Ok(game)
     }
 
-    fn create_random_players(num_players: usize, board: &mut Board) -> Vec<Player> {
-        let initial_armies = match num_players {
-            3 => 35,
-            4 => 30,
-            5 => 25,
-            _ => 20, // Default to 20 armies for 6 or more players
+    // Picks up a scenario mid-game, expects a state that passes validation
+    fn restore_state(&mut self, state: StateConfig) {
+        self.current_turn = state.current_turn;
+        self.round = state.round;
+        self.turn_phase = state.turn_phase;
+        self.reinforcement_armies = match state.reinforcement_armies {
+            Some(armies) => armies,
+            None if self.turn_phase == TurnPhase::Reinforce => {
+                self.calculate_reinforcements(self.current_turn)
+            }
+            None => 0,
         };
+        self.initial_reinforcement_armies = state
+            .initial_reinforcement_armies
+            .unwrap_or(self.reinforcement_armies);
+        self.conquered_territory = state.conquered_territory;
+        self.discard_pile = state.discard_pile.iter().map(CardConfig::to_card).collect();
+        self.active_players = (0..self.players.len())
+            .filter(|id| !state.defeated_players.contains(id))
+            .collect();
+        self.defeated_players = state.defeated_players;
+        if let Some(last_attack) = state.last_attack {
+            self.last_attack_from = Some(last_attack.from);
+            self.last_attack_to = Some(last_attack.to);
+            self.last_attack_dice = Some(last_attack.dice);
+        }
+    }
 
-        let mut players = Vec::new();
-        for i in 0..num_players {
-            players.push(Player::new(i, &format!("Player {}", i + 1)));
+    // A shuffled deck without the cards that are already held or discarded
+    fn remaining_deck<'a>(
+        board: &Board,
+        held: impl Iterator<Item = &'a Card>,
+        rng: &mut StdRng,
+    ) -> Vec<Card> {
+        let mut deck = Game::create_deck(board, rng);
+        for card in held {
+            if let Some(index) = deck.iter().position(|c| c.territory == card.territory) {
+                deck.remove(index);
+            }
         }
+        deck
+    }
 
-        // Shuffle and distribute territories
-        board.shuffle_and_distribute_territories(&mut players);
+    // Rebuild a playable game from a state snapshot, e.g. for bots that search ahead.
+    // The snapshot does not include the deck, so it is dealt again from the cards not in any hand.
+    pub fn from_game_state(state: &GameState) -> Self {
+        let mut rng = StdRng::from_entropy();
+        let cards_in_hands = state.players.iter().flat_map(|player| player.cards.iter());
+        let deck = Game::remaining_deck(&state.board, cards_in_hands, &mut rng);
 
-        // Calculate the total initial armies already on the board
-        let armies_on_board: Vec<u16> = players
+        let (last_attack_from, last_attack_to, last_attack_dice) = state
+            .possible_actions
             .iter()
-            .map(|player| {
-                player
-                    .territories
-                    .iter()
-                    .map(|territory| player.get_armies(territory))
-                    .sum()
+            .find_map(|action| match action {
+                Action::MoveArmies {
+                    from,
+                    to,
+                    min_armies,
+                    ..
+                } => Some((Some(from.clone()), Some(to.clone()), Some(*min_armies))),
+                _ => None,
             })
-            .collect();
-
-        // Distribute remaining armies to ensure each player reaches initial_armies threshold
-        let mut remaining_armies: Vec<u16> = armies_on_board
-            .iter()
-            .map(|&armies| initial_armies - armies)
-            .collect();
-
-        while remaining_armies.iter().any(|&armies| armies > 0) {
-            for (player_index, player) in players.iter_mut().enumerate() {
-                if remaining_armies[player_index] == 0 {
-                    continue;
-                }
+            .unwrap_or((None, None, None));
+
+        Self {
+            players: state.players.clone(),
+            board: state.board.clone(),
+            current_turn: state.current_turn,
+            round: state.round,
+            turn_phase: state.turn_phase.clone(),
+            reinforcement_armies: state.reinforcement_armies,
+            initial_reinforcement_armies: state.initial_reinforcement_armies,
+            deck,
+            discard_pile: vec![],
+            conquered_territory: state.conquered_territory,
+            defeated_players: state.defeated_players.clone(),
+            last_attack_from,
+            last_attack_to,
+            last_attack_dice,
+            active_players: (0..state.players.len())
+                .filter(|id| !state.defeated_players.contains(id))
+                .collect(),
+            conquer_probs: state.conquer_probs.clone(),
+            prob_cache: HashMap::new(),
+            prob_table: ProbabilityTable::embedded(),
+            rng,
+        }
+    }
 
-                let territories: Vec<String> = player.territories.iter().cloned().collect();
-                for territory in territories {
-                    if remaining_armies[player_index] > 0 {
-                        player.reinforce(&territory, 1);
-                        remaining_armies[player_index] -= 1;
-                    } else {
-                        break;
-                    }
-                }
-            }
+    fn create_random_players(
+        num_players: usize,
+        initial_armies: u16,
+        distribution: &DistributionConfig,
+        board: &mut Board,
+        rng: &mut StdRng,
+    ) -> Result<Vec<Player>, String> {
+        let mut players = Vec::new();
+        for i in 0..num_players {
+            players.push(Player::new(i, &format!("Player {}", i + 1)));
         }
 
-        players
+        distribution.distribute(board, &mut players, initial_armies, rng)?;
+        Ok(players)
     }
 
     pub fn reinforce(
@@ -281,11 +443,11 @@ impl Game {
     
             // Roll dice
             let mut attacker_rolls: Vec<u16> = (0..num_dice)
-                .map(|_| thread_rng().gen_range(1..=6))
+                .map(|_| self.rng.gen_range(1..=6))
                 .collect();
             let defender_dice = defender.get_armies(to_territory).min(2);
             let defender_rolls: Vec<u16> = (0..defender_dice)
-                .map(|_| thread_rng().gen_range(1..=6))
+                .map(|_| self.rng.gen_range(1..=6))
                 .collect();
     
             attacker_rolls.sort_unstable_by(|a, b| b.cmp(a));
@@ -404,13 +566,45 @@ impl Game {
         Ok(())
     }
 
+    // Apply an action as chosen from get_possible_actions. The upper limits in the action
+    // (max_armies, max_dice) are used as the amount, so a caller can lower them first.
+    pub fn apply_action(&mut self, player_id: usize, action: &Action) -> Result<(), String> {
+        match action {
+            Action::Reinforce {
+                territory,
+                max_armies,
+            } => self.reinforce(player_id, territory, *max_armies),
+            Action::Attack { from, to, max_dice } => {
+                self.attack(player_id, from, to, *max_dice, false)
+            }
+            Action::Fortify {
+                from,
+                to,
+                max_armies,
+            } => self.fortify(player_id, from, to, *max_armies),
+            Action::TradeCards { card_indices } => self
+                .trade_cards(player_id, card_indices.clone())
+                .map(|_| ()),
+            Action::MoveArmies {
+                from,
+                to,
+                max_armies,
+                ..
+            } => self.move_armies_after_attack(player_id, from, to, *max_armies),
+            Action::EndPhase => {
+                self.advance_phase();
+                Ok(())
+            }
+        }
+    }
+
     pub fn calculate_reinforcements(&self, player_id: usize) -> u16 {
         let player = &self.players[player_id];
         let territories_owned = player.territories.len() as u16;
         let base_reinforcements = std::cmp::max(territories_owned / 3, 3);
 
         // Calculate continent bonuses
-        let mut continent_bonus = 0 as u16;
+        let mut continent_bonus: u16 = 0;
         for continent in self.board.continents.values() {
             if continent
                 .territories
@@ -431,115 +625,27 @@ impl Game {
     }
 
     // Calculate the probability of attacker winning after all possible rolls
-    fn calculate_conquer_probability(&mut self, attacker_armies: u16, defender_armies: u16) -> f64 {
-
-    // Check if the probability is already in the cache
-    if let Some(&prob) = self.prob_cache.get(&(attacker_armies, defender_armies)) {
-        return prob;
+    pub fn calculate_conquer_probability(&mut self, attacker_armies: u16, defender_armies: u16) -> f64 {
+        if let Some(prob) = self.prob_table.get(attacker_armies, defender_armies) {
+            return prob;
+        }
+        *self
+            .prob_cache
+            .entry((attacker_armies, defender_armies))
+            .or_insert_with(|| battle::conquer_probability(attacker_armies, defender_armies))
     }
-    
-        fn calculate_attack_probability(attacker_armies: u32, defender_armies: u32) -> f64 {
-            if attacker_armies <= 1 {
-                return 0.0;
-            }
-            if defender_armies == 0 {
-                return 1.0;
-            }
-
-            let a = attacker_armies as usize;
-            let d = defender_armies as usize;
-
-            if a >= 3 && d >= 2 {
-                let p_win2 = p_win2(a);
-                let p_lose2 = p_lose2(a);
-                let p_win1_lose1 = 1.0 - p_win2 - p_lose2;
-                
-                p_win2 * calculate_attack_probability(a as u32, d as u32 - 2)
-                + p_win1_lose1 * calculate_attack_probability(a as u32 - 1, d as u32 - 1)
-                + p_lose2 * calculate_attack_probability(a as u32 - 2, d as u32)
-            } else {
-                let p_win1 = p_win1(a, d);
-                
-                p_win1 * calculate_attack_probability(a as u32, d as u32 - 1)
-                + (1.0 - p_win1) * calculate_attack_probability(a as u32 - 1, d as u32)
-            }
-        }
-
-        fn dice_distribution(n: usize) -> Vec<(usize, usize)> {
-            match n {
-                1 => (1..=6).map(|i| (1, i)).collect(),
-                2 => (1..=6)
-                    .flat_map(|i| (i..=6).map(move |j| (i, j)))
-                    .collect(),
-                3 => (1..=6)
-                    .flat_map(|i| (i..=6).flat_map(move |j| (j..=6).map(move |k| (j, k))))
-                    .collect(),
-                _ => panic!("Invalid number of dice"),
-            }
-        }
-
-        fn p_win2(a: usize) -> f64 {
-            let attacker_dist = dice_distribution(std::cmp::min(a - 1, 3));
-            let defender_dist = dice_distribution(2);
-            let total = attacker_dist.len() * defender_dist.len();
-            let wins = attacker_dist
-                .iter()
-                .flat_map(|&(a1, a2)| {
-                    defender_dist
-                        .iter()
-                        .filter(move |&&(d1, d2)| a1 > d1 && a2 > d2)
-                })
-                .count();
-            wins as f64 / total as f64
-        }
-
-        fn p_lose2(a: usize) -> f64 {
-            let attacker_dist = dice_distribution(std::cmp::min(a - 1, 3));
-            let defender_dist = dice_distribution(2);
-            let total = attacker_dist.len() * defender_dist.len();
-            let losses = attacker_dist
-                .iter()
-                .flat_map(|&(a1, a2)| {
-                    defender_dist
-                        .iter()
-                        .filter(move |&&(d1, d2)| a1 <= d1 && a2 <= d2)
-                })
-                .count();
-            losses as f64 / total as f64
-        }
-
-        fn p_win1(a: usize, d: usize) -> f64 {
-            let attacker_dist = dice_distribution(std::cmp::min(a - 1, 3));
-            let defender_dist = dice_distribution(std::cmp::min(d, 2));
-            let total = attacker_dist.len() * defender_dist.len();
-            let wins = attacker_dist
-                .iter()
-                .flat_map(|&(_, a2)| defender_dist.iter().filter(move |&&(_, d2)| a2 > d2))
-                .count();
-            wins as f64 / total as f64
-        }
-
-        // Call the recursive function to calculate probability
-        let probability = calculate_attack_probability(attacker_armies as u32, defender_armies as u32);
-        let rounded_probability = (probability * 10000.0).round() / 100.0;
-
-        // Store the computed probability in the cache
-        self.prob_cache.insert((attacker_armies, defender_armies), rounded_probability);
-        
-        rounded_probability
 
+    // Restart the dice and card rng, e.g. to replay attacks from a known state
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
     }
 
-    pub fn load_conquer_probabilities(&mut self, filename: &str) {
-        let mut file = File::open(filename).expect("Failed to open probabilities file");
-        let mut encoded = Vec::new();
-        file.read_to_end(&mut encoded).expect("Failed to read file");
-
-        let probability_cache: ProbabilityCache = bincode::deserialize(&encoded).expect("Failed to deserialize data");
-        self.prob_cache = probability_cache.cache;
+    // Use another table than the one compiled into the binary, e.g. a larger precomputed one
+    pub fn set_probability_table(&mut self, table: Arc<ProbabilityTable>) {
+        self.prob_table = table;
+        self.prob_cache.clear();
     }
 
-
     // New function to calculate conquer probabilities
     fn calculate_conquer_probabilities(&mut self) -> Vec<(String, String, f64)> {
         let mut conquer_probs = Vec::new();
@@ -636,8 +742,8 @@ impl Game {
             actions.push(Action::MoveArmies {
                 from: from_territory.clone(),
                 to: to_territory.clone(),
-                max_armies: max_armies,
-                min_armies: min_armies,
+                max_armies,
+                min_armies,
             });
         }
         actions
@@ -1,16 +1,88 @@
 use crate::board::Board;
 use crate::card::{Card, CardKind};
-use crate::continent::Continent;
+use crate::distribution::DistributionConfig;
+use crate::game::Game;
+use crate::layout::MapLayout;
+use crate::map::{MapDefinition, CLASSIC_MAP};
+pub use crate::map::{ContinentConfig, TerritoryConfig};
 use crate::player::Player;
-use crate::territory::Territory;
+use crate::turn_phase::TurnPhase;
+use crate::validation::describe;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::BTreeMap;
+use std::path::Path;
 
+// Name of the inline map in scenarios exported from a game
+const SAVED_MAP_NAME: &str = "Saved game";
+
+// A scenario: the map to play on, optionally the starting positions and, for a game that
+// starts mid-way, where it stands
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameConfig {
-    pub players: Vec<PlayerConfig>,
+    // Built-in map name or map file, relative to the scenario file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<String>,
+    // The board can also be given inline instead of referencing a map
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub territories: Vec<TerritoryConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub continents: Vec<ContinentConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub starting_armies: BTreeMap<usize, u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<MapLayout>,
+    // Without players the territories are dealt randomly
+    #[serde(default)]
+    pub players: Vec<PlayerConfig>,
+    // How they are dealt, round-robin per continent with armies spread evenly by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<DistributionConfig>,
+    // Without a state the game starts with the first player's reinforcements
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<StateConfig>,
+}
+
+// The rest of a game position, requires players
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateConfig {
+    #[serde(default)]
+    pub current_turn: usize,
+    #[serde(default)]
+    pub round: usize,
+    #[serde(default = "default_turn_phase")]
+    pub turn_phase: TurnPhase,
+    // Armies left to place, the current player's reinforcements when missing
+    #[serde(default)]
+    pub reinforcement_armies: Option<u16>,
+    #[serde(default)]
+    pub initial_reinforcement_armies: Option<u16>,
+    // Whether the current player has conquered a territory this turn and draws a card
+    #[serde(default)]
+    pub conquered_territory: bool,
+    // In draw order, the last card is drawn first. Without a deck the cards that are in no
+    // hand or discard pile are shuffled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deck: Option<Vec<CardConfig>>,
+    #[serde(default)]
+    pub discard_pile: Vec<CardConfig>,
+    #[serde(default)]
+    pub defeated_players: Vec<usize>,
+    // The conquest waiting for armies to move in, required in the MoveArmies phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_attack: Option<LastAttackConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastAttackConfig {
+    pub from: String,
+    pub to: String,
+    // Dice rolled in the conquering attack, the minimum number of armies to move
+    pub dice: u16,
+}
+
+fn default_turn_phase() -> TurnPhase {
+    TurnPhase::Reinforce
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,75 +105,156 @@ pub struct CardConfig {
     pub kind: CardKind,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TerritoryConfig {
-    pub name: String,
-    pub continent: String,
-    pub adjacent_territories: Vec<String>,
-}
+impl CardConfig {
+    pub fn to_card(&self) -> Card {
+        Card::new(self.territory.clone(), self.kind.clone())
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ContinentConfig {
-    pub name: String,
-    pub bonus_armies: u16,
-    pub territories: Vec<String>,
+    pub fn from_card(card: &Card) -> Self {
+        Self {
+            territory: card.territory.clone(),
+            kind: card.kind.clone(),
+        }
+    }
 }
 
 impl GameConfig {
-    pub fn to_board_and_players(&self) -> (Board, Vec<Player>) {
-        let mut board = Board::new();
-        let mut players = Vec::new();
-        let mut all_territories = HashSet::new();
-        let mut assigned_territories = HashSet::new();
-        let mut duplicate_territories = HashSet::new();
-
-        for continent_config in &self.continents {
-            let mut continent = Continent::new(&continent_config.name, continent_config.bonus_armies);
-            for territory_name in &continent_config.territories {
-                continent.add_territory(territory_name);
-                all_territories.insert(territory_name.clone());
-            }
-            board.add_continent(continent);
+    // Scenario on a map with territories dealt randomly
+    pub fn from_map(map: &str) -> Self {
+        Self {
+            map: Some(map.to_string()),
+            territories: vec![],
+            continents: vec![],
+            starting_armies: BTreeMap::new(),
+            layout: None,
+            players: vec![],
+            distribution: None,
+            state: None,
         }
+    }
 
-        for territory_config in &self.territories {
-            let mut territory = Territory::new(&territory_config.name, &territory_config.continent);
-            for adjacent in &territory_config.adjacent_territories {
-                territory.add_adjacent(adjacent);
-            }
-            board.add_territory(territory);
+    // Scenario with the board inline, e.g. for a generated map
+    pub fn from_definition(map: MapDefinition) -> Self {
+        Self {
+            map: Some(map.name),
+            territories: map.territories,
+            continents: map.continents,
+            starting_armies: map.starting_armies,
+            layout: map.layout,
+            players: vec![],
+            distribution: None,
+            state: None,
+        }
+    }
+
+    // The complete position of a running game, with the board inline
+    pub fn from_game(game: &Game) -> Self {
+        let players = game
+            .players
+            .iter()
+            .map(|player| PlayerConfig {
+                id: player.id,
+                name: player.name.clone(),
+                territories: player
+                    .territories
+                    .iter()
+                    .sorted()
+                    .map(|territory| PlayerTerritoryConfig {
+                        name: territory.clone(),
+                        armies: player.get_armies(territory),
+                    })
+                    .collect(),
+                cards: player.cards.iter().map(CardConfig::from_card).collect(),
+            })
+            .collect();
+        let last_attack = match (&game.last_attack_from, &game.last_attack_to, game.last_attack_dice) {
+            (Some(from), Some(to), Some(dice)) => Some(LastAttackConfig {
+                from: from.clone(),
+                to: to.clone(),
+                dice,
+            }),
+            _ => None,
+        };
+        Self {
+            players,
+            state: Some(StateConfig {
+                current_turn: game.current_turn,
+                round: game.round,
+                turn_phase: game.turn_phase.clone(),
+                reinforcement_armies: Some(game.reinforcement_armies),
+                initial_reinforcement_armies: Some(game.initial_reinforcement_armies),
+                conquered_territory: game.conquered_territory,
+                deck: Some(game.deck.iter().map(CardConfig::from_card).collect()),
+                discard_pile: game.discard_pile.iter().map(CardConfig::from_card).collect(),
+                defeated_players: game.defeated_players.clone(),
+                last_attack,
+            }),
+            ..Self::from_definition(MapDefinition::from_board(&game.board, SAVED_MAP_NAME))
+        }
+    }
+
+    // The inline board, otherwise the referenced map, otherwise the classic map
+    pub fn map_definition(&self) -> Result<MapDefinition, String> {
+        if !self.territories.is_empty() {
+            return Ok(MapDefinition {
+                name: self.map.clone().unwrap_or_else(|| "Custom".to_string()),
+                territories: self.territories.clone(),
+                continents: self.continents.clone(),
+                starting_armies: self.starting_armies.clone(),
+                layout: self.layout.clone(),
+            });
         }
+        let reference = self.map.as_deref().unwrap_or(CLASSIC_MAP);
+        MapDefinition::resolve(reference, None)
+            .map_err(|e| format!("Unable to load map {}: {}", reference, e))
+    }
 
+    // Expects a scenario that passes validate
+    pub fn to_board_and_players(&self) -> Result<(Board, Vec<Player>), String> {
+        let board = self.map_definition()?.to_board();
+        let mut players = Vec::new();
         for player_config in &self.players {
             let mut player = Player::new(player_config.id, &player_config.name);
             for territory in &player_config.territories {
-                if !assigned_territories.insert(territory.name.clone()) {
-                    duplicate_territories.insert(territory.name.clone());
-                }
                 player.add_territory(&territory.name);
                 player.set_armies(&territory.name, territory.armies);
             }
             for card in &player_config.cards {
-                let card = Card::new(card.territory.clone(), card.kind.clone());
-                player.cards.push(card);
+                player.cards.push(card.to_card());
             }
             players.push(player);
         }
+        Ok((board, players))
+    }
 
-        // Assert no duplicate territories
-        assert!(duplicate_territories.is_empty(), "Duplicate territories found: {:?}", duplicate_territories);
-
-        // Assert all territories are assigned
-        for territory in &all_territories {
-            assert!(assigned_territories.contains(territory), "Territory not assigned: {}", territory);
+    // Loads the scenario together with the map it references and validates both
+    pub fn load_from_file(filename: &str) -> Result<Self, String> {
+        let config = Self::read_from_file(filename)?;
+        let errors = config.validate();
+        if !errors.is_empty() {
+            return Err(format!("{}: {}", filename, describe(&errors)));
         }
+        Ok(config)
+    }
 
-        (board, players)
+    pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(filename, data).map_err(|e| format!("{}: {}", filename, e))
     }
 
-    pub fn load_from_file(filename: &str) -> Result<Self, std::io::Error> {
-        let data = std::fs::read_to_string(filename)?;
-        let config: GameConfig = serde_json::from_str(&data)?;
+    // Like load_from_file, without validating
+    pub fn read_from_file(filename: &str) -> Result<Self, String> {
+        let data = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let mut config: GameConfig =
+            serde_json::from_str(&data).map_err(|e| format!("{}: {}", filename, e))?;
+        if config.territories.is_empty() {
+            let reference = config.map.as_deref().unwrap_or(CLASSIC_MAP);
+            let map = MapDefinition::resolve(reference, Path::new(filename).parent())?;
+            config.territories = map.territories;
+            config.continents = map.continents;
+            config.starting_armies = map.starting_armies;
+            config.layout = map.layout;
+        }
         Ok(config)
     }
 }
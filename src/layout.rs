@@ -0,0 +1,57 @@
+// layout.rs
+use serde::{Deserialize, Serialize};
+
+// Display metadata so UIs can draw any map without hard-coding it. The game itself never
+// reads it. Coordinates are in the map's own units, with y pointing down as in SVG.
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+// Size of the drawing area and the images that go with the map. References are URLs or
+// paths relative to the map file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MapLayout {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<f64>,
+    // Drawn below the territories, e.g. the map artwork of a Domination map
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_image: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TerritoryLayout {
+    // Where the army count goes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<Point>,
+    // Outline of the territory, closed implicitly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub polygon: Vec<Point>,
+    // Outline as SVG path data, for shapes a polygon can't describe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    // Position of the name relative to the center
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_offset: Option<Point>,
+}
+
+impl TerritoryLayout {
+    pub fn at(x: f64, y: f64) -> Self {
+        Self {
+            center: Some(Point::new(x, y)),
+            ..Self::default()
+        }
+    }
+}
@@ -1,9 +1,26 @@
 // lib.rs
+pub mod advisor;
+pub mod battle;
 pub mod board;
+pub mod bot;
 pub mod card;
 pub mod continent;
+pub mod distribution;
+pub mod domination;
+pub mod engine;
+pub mod environment;
 pub mod game;
 pub mod game_config;
+pub mod layout;
+pub mod map;
+pub mod map_analysis;
+pub mod map_generator;
+pub mod mcts;
 pub mod player;
+pub mod probability_table;
+pub mod render;
+pub mod simulation;
 pub mod territory;
+pub mod tournament;
 pub mod turn_phase;
+pub mod validation;
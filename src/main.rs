@@ -5,20 +5,44 @@ use rocket::serde::json::Json;
 use rocket::State;
 use rocket_cors::{AllowedOrigins, CorsOptions};
 use serde::Serialize;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
 use tokio::sync::{mpsc, oneshot, Mutex};
 //use console_subscriber;
+use rocket::http::ContentType;
 use rocket::response::content;
 
 use risk_board_game_server::{
+    advisor::{advise_reinforcements, ReinforcementAdvice},
+    battle::{battle_outcome, plan_attack_path, BattleOutcome, PathOutcome},
+    bot::{default_action, Bot, BotRegistry, Seat},
+    engine::ENGINES_FILE,
     game::{Game, GameState},
     game_config::GameConfig,
+    distribution::DistributionConfig,
+    map::{MapDefinition, MapSummary, CLASSIC_MAP},
+    map_generator::{generate_map, GeneratorConfig},
+    probability_table::ProbabilityTable,
+    turn_phase::TurnPhase,
+    validation::describe,
 };
 
+// Environment variable with the path of a probability table to use instead of the embedded one
+const PROBABILITY_TABLE_ENV: &str = "RISK_PROBABILITY_TABLE";
+
+// Upper bound on bot moves per task, a game between bots only continues with the next request
+const MAX_BOT_ACTIONS: usize = 10_000;
+
+// Largest stacks accepted by /battle-outcome and /attack-path, the work grows with
+// attacker * defender
+const MAX_BATTLE_ARMIES: u16 = 1000;
+
 #[derive(Serialize, Debug)]
 struct GameResponse {
     game_state: Option<GameState>,
     error: Option<String>,
+    // Only filled in for Request::GetScenario
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scenario: Option<GameConfig>,
 }
 
 impl GameResponse {
@@ -26,6 +50,7 @@ impl GameResponse {
         GameResponse {
             game_state: Some(game_state),
             error: None,
+            scenario: None,
         }
     }
 
@@ -33,10 +58,42 @@ impl GameResponse {
         GameResponse {
             game_state: Some(game_state),
             error: Some(error),
+            scenario: None,
         }
     }
 }
 
+#[derive(Serialize, Debug)]
+struct ScenarioResponse {
+    scenario: Option<GameConfig>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct AdviceResponse {
+    advice: Option<ReinforcementAdvice>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct BattleResponse {
+    outcome: Option<BattleOutcome>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct AttackPathResponse {
+    plan: Option<PathOutcome>,
+    error: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+struct AttackPathData {
+    from_territory: String,
+    path: Vec<String>,
+    max_dice: Option<u16>,
+}
+
 #[derive(serde::Deserialize, Clone)]
 struct ReinforceData {
     player_id: usize,
@@ -90,7 +147,16 @@ struct TradeCardsData {
 #[derive(serde::Deserialize, Clone)]
 struct NewGameData {
     config_file: Option<String>,
+    // Name of a built-in map from GET /maps, as an alternative to a config file
+    map: Option<String>,
+    // Or a new map built by the generator
+    generated_map: Option<GeneratorConfig>,
+    // Or a scenario given inline, e.g. one exported from GET /scenario
+    scenario: Option<Box<GameConfig>>,
+    // How the territories are dealt, overrides the scenario's
+    distribution: Option<DistributionConfig>,
     num_players: Option<usize>,
+    seats: Option<Vec<Seat>>,
 }
 
 #[derive(Clone)]
@@ -104,6 +170,7 @@ enum Request {
     AdvancePhase,
     NewGame(NewGameData),
     GetGameState,
+    GetScenario,
 }
 
 struct RequestWithResponse {
@@ -113,6 +180,7 @@ struct RequestWithResponse {
 
 struct SharedState {
     sender: mpsc::Sender<RequestWithResponse>,
+    bots: Arc<BotRegistry>,
 }
 
 #[derive(Serialize)]
@@ -135,6 +203,12 @@ fn api_documentation() -> content::RawJson<String> {
             method: "GET".to_string(),
             description: "Get the current state of the game".to_string(),
         },
+        ApiEndpoint {
+            path: "/scenario".to_string(),
+            method: "GET".to_string(),
+            description: "Export the complete position of the current game as a scenario"
+                .to_string(),
+        },
         ApiEndpoint {
             path: "/reinforce".to_string(),
             method: "POST".to_string(),
@@ -170,10 +244,56 @@ fn api_documentation() -> content::RawJson<String> {
             method: "POST".to_string(),
             description: "Advance to the next game phase".to_string(),
         },
+        ApiEndpoint {
+            path: "/reinforcement-advice".to_string(),
+            method: "GET".to_string(),
+            description: "Recommend how the current player should split their reinforcements"
+                .to_string(),
+        },
+        ApiEndpoint {
+            path: "/battle-outcome?attacker=<armies>&defender=<armies>&max_dice=<dice>"
+                .to_string(),
+            method: "GET".to_string(),
+            description: "Distribution of remaining armies and expected losses of a battle"
+                .to_string(),
+        },
+        ApiEndpoint {
+            path: "/attack-path".to_string(),
+            method: "POST".to_string(),
+            description: "Probability of conquering a chain of territories in one sweep"
+                .to_string(),
+        },
+        ApiEndpoint {
+            path: "/board.dot".to_string(),
+            method: "GET".to_string(),
+            description: "The board as a Graphviz graph, clustered by continent and colored by owner"
+                .to_string(),
+        },
+        ApiEndpoint {
+            path: "/board.svg".to_string(),
+            method: "GET".to_string(),
+            description: "The board rendered as SVG with owners and army counts".to_string(),
+        },
+        ApiEndpoint {
+            path: "/bots".to_string(),
+            method: "GET".to_string(),
+            description: "List the bots that can be used as seats".to_string(),
+        },
+        ApiEndpoint {
+            path: "/maps".to_string(),
+            method: "GET".to_string(),
+            description: "List the built-in maps with their territory and continent counts"
+                .to_string(),
+        },
+        ApiEndpoint {
+            path: "/maps/<name>".to_string(),
+            method: "GET".to_string(),
+            description: "Definition of a built-in map including its layout".to_string(),
+        },
         ApiEndpoint {
             path: "/new-game".to_string(),
             method: "POST".to_string(),
-            description: "Start a new game with optional configuration".to_string(),
+            description: "Start a new game with optional configuration and bot seats".to_string(),
         },
     ];
 
@@ -223,11 +343,134 @@ async fn new_game(state: &State<SharedState>, data: Json<NewGameData>) -> Json<G
     send_request_and_wait(state, Request::NewGame(data.into_inner())).await
 }
 
+#[get("/bots")]
+fn list_bots(state: &State<SharedState>) -> Json<Vec<String>> {
+    Json(state.bots.names())
+}
+
+#[get("/maps")]
+fn list_maps() -> Json<Vec<MapSummary>> {
+    Json(MapDefinition::catalog())
+}
+
+#[get("/maps/<name>")]
+fn get_map(name: &str) -> Option<Json<MapDefinition>> {
+    MapDefinition::builtin(name).map(Json)
+}
+
 #[get("/game-state")]
 async fn game_state(state: &State<SharedState>) -> Json<GameResponse> {
     send_request_and_wait(state, Request::GetGameState).await
 }
 
+#[get("/scenario")]
+async fn scenario(state: &State<SharedState>) -> Json<ScenarioResponse> {
+    let response = send_request_and_wait(state, Request::GetScenario).await;
+    Json(ScenarioResponse {
+        error: response
+            .scenario
+            .is_none()
+            .then(|| "Game state is not available".to_string()),
+        scenario: response.into_inner().scenario,
+    })
+}
+
+#[get("/reinforcement-advice")]
+async fn reinforcement_advice(state: &State<SharedState>) -> Json<AdviceResponse> {
+    let response = send_request_and_wait(state, Request::GetGameState).await;
+    let result = match &response.game_state {
+        Some(game_state) => advise_reinforcements(game_state),
+        None => Err("Game state is not available".to_string()),
+    };
+    Json(match result {
+        Ok(advice) => AdviceResponse {
+            advice: Some(advice),
+            error: None,
+        },
+        Err(e) => AdviceResponse {
+            advice: None,
+            error: Some(e),
+        },
+    })
+}
+
+#[get("/battle-outcome?<attacker>&<defender>&<max_dice>")]
+fn battle(attacker: u16, defender: u16, max_dice: Option<u16>) -> Json<BattleResponse> {
+    let result = if attacker > MAX_BATTLE_ARMIES || defender > MAX_BATTLE_ARMIES {
+        Err(format!("Armies are limited to {}", MAX_BATTLE_ARMIES))
+    } else {
+        battle_outcome(attacker, defender, max_dice.unwrap_or(3))
+    };
+    Json(match result {
+        Ok(outcome) => BattleResponse {
+            outcome: Some(outcome),
+            error: None,
+        },
+        Err(e) => BattleResponse {
+            outcome: None,
+            error: Some(e),
+        },
+    })
+}
+
+#[post("/attack-path", data = "<data>")]
+async fn attack_path(
+    data: Json<AttackPathData>,
+    state: &State<SharedState>,
+) -> Json<AttackPathResponse> {
+    let response = send_request_and_wait(state, Request::GetGameState).await;
+    let result = match &response.game_state {
+        Some(game_state) if too_many_armies(game_state, &data.from_territory, &data.path) => {
+            Err(format!("Armies are limited to {}", MAX_BATTLE_ARMIES))
+        }
+        Some(game_state) => plan_attack_path(
+            game_state,
+            &data.from_territory,
+            &data.path,
+            data.max_dice.unwrap_or(3),
+        ),
+        None => Err("Game state is not available".to_string()),
+    };
+    Json(match result {
+        Ok(plan) => AttackPathResponse {
+            plan: Some(plan),
+            error: None,
+        },
+        Err(e) => AttackPathResponse {
+            plan: None,
+            error: Some(e),
+        },
+    })
+}
+
+// The same limit as /battle-outcome for the attacking stack and every defender on the path
+fn too_many_armies(game_state: &GameState, from: &String, path: &[String]) -> bool {
+    std::iter::once(from).chain(path).any(|territory| {
+        game_state
+            .players
+            .iter()
+            .any(|player| player.get_armies(territory) > MAX_BATTLE_ARMIES)
+    })
+}
+
+#[get("/board.dot")]
+async fn board_dot(state: &State<SharedState>) -> content::RawText<String> {
+    let response = send_request_and_wait(state, Request::GetGameState).await;
+    content::RawText(match &response.game_state {
+        Some(game_state) => game_state.board.to_dot(&game_state.players),
+        None => "Game state is not available".to_string(),
+    })
+}
+
+#[get("/board.svg")]
+async fn board_svg(state: &State<SharedState>) -> (ContentType, String) {
+    let response = send_request_and_wait(state, Request::GetGameState).await;
+    match &response.game_state {
+        Some(game_state) => (ContentType::SVG, game_state.board.to_svg(&game_state.players)),
+        None => (ContentType::Plain, "Game state is not available".to_string()),
+    }
+}
+
 async fn send_request_and_wait(state: &State<SharedState>, request: Request) -> Json<GameResponse> {
     let (response_sender, response_receiver) = oneshot::channel();
     state
@@ -243,14 +486,123 @@ async fn send_request_and_wait(state: &State<SharedState>, request: Request) ->
     Json(response)
 }
 
-async fn worker_task(mut receiver: mpsc::Receiver<RequestWithResponse>, game: Arc<Mutex<Game>>) {
+// Bots of the current game. A blocking task plays them so that slow bots don't hold up the
+// worker, the game is only locked while reading the state and applying a move.
+#[derive(Default)]
+struct BotSeats {
+    seats: Vec<Option<Box<dyn Bot>>>,
+    // Bumped with every new game, moves chosen for an earlier game are dropped
+    generation: u64,
+    // Whether a task is playing the bots, only changed while holding the game lock
+    running: bool,
+    // Seat whose bot is out of it while choosing a move
+    thinking: Option<usize>,
+}
+
+impl BotSeats {
+    fn bot_on_turn(&self, game: &Game) -> bool {
+        game.turn_phase != TurnPhase::GameOver
+            && (self.thinking == Some(game.current_turn)
+                || matches!(self.seats.get(game.current_turn), Some(Some(_))))
+    }
+
+    // Takes the bot on turn out of its seat together with the state it has to move in
+    fn take_turn(&mut self, game: &mut Game) -> Option<(GameState, Box<dyn Bot>)> {
+        if !self.bot_on_turn(game) {
+            return None;
+        }
+        let state = game.get_game_state();
+        if state.possible_actions.is_empty() {
+            return None;
+        }
+        let bot = self.seats[game.current_turn].take()?;
+        self.thinking = Some(game.current_turn);
+        Some((state, bot))
+    }
+}
+
+// Let bots play until a human seat is on turn or the game is over
+fn play_bot_turns(shared_game: Arc<Mutex<Game>>, bot_seats: Arc<StdMutex<BotSeats>>, generation: u64) {
+    for _ in 0..MAX_BOT_ACTIONS {
+        let (state, mut bot) = {
+            let mut game = shared_game.blocking_lock();
+            let mut seats = bot_seats.lock().unwrap();
+            if seats.generation != generation {
+                return;
+            }
+            match seats.take_turn(&mut game) {
+                Some(turn) => turn,
+                None => {
+                    seats.running = false;
+                    return;
+                }
+            }
+        };
+        let action = bot.choose_action(&state);
+
+        let mut game = shared_game.blocking_lock();
+        let mut seats = bot_seats.lock().unwrap();
+        if seats.generation != generation {
+            return;
+        }
+        let player_id = state.current_turn;
+        seats.thinking = None;
+        // Moves for bot seats are refused, but a new game may have started meanwhile
+        let result = if game.current_turn == player_id && game.turn_phase == state.turn_phase {
+            game.apply_action(player_id, &action).or_else(|e| {
+                eprintln!(
+                    "Bot '{}' for player {} failed to apply {:?}, playing the default action: {}",
+                    bot.name(),
+                    player_id,
+                    action,
+                    e
+                );
+                game.apply_action(player_id, &default_action(&state))
+            })
+        } else {
+            Ok(())
+        };
+        let name = bot.name().to_string();
+        seats.seats[player_id] = Some(bot);
+        if let Err(e) = result {
+            eprintln!("Bot '{}' for player {} is stuck: {}", name, player_id, e);
+            seats.running = false;
+            return;
+        }
+    }
+
+    // The next request starts a new task
+    let _game = shared_game.blocking_lock();
+    let mut seats = bot_seats.lock().unwrap();
+    if seats.generation == generation {
+        seats.running = false;
+    }
+}
+
+async fn worker_task(
+    mut receiver: mpsc::Receiver<RequestWithResponse>,
+    shared_game: Arc<Mutex<Game>>,
+    bots: Arc<BotRegistry>,
+    prob_table: Arc<ProbabilityTable>,
+) {
+    let bot_seats = Arc::new(StdMutex::new(BotSeats::default()));
+
     while let Some(RequestWithResponse {
         request,
         response_sender,
     }) = receiver.recv().await
     {
-        let mut game = game.lock().await;
+        let mut game = shared_game.lock().await;
+        let is_move = !matches!(
+            request,
+            Request::NewGame(_) | Request::GetGameState | Request::GetScenario
+        );
+        let bot_on_turn = bot_seats.lock().unwrap().bot_on_turn(&game);
         let response = match request {
+            _ if is_move && bot_on_turn => GameResponse::error(
+                game.get_game_state(),
+                "A bot is on turn, wait for it to finish".to_string(),
+            ),
             Request::Reinforce(data) => {
                 match game.reinforce(data.player_id, &data.territory, data.num_armies) {
                     Ok(_) => GameResponse::success(game.get_game_state()),
@@ -319,15 +671,82 @@ async fn worker_task(mut receiver: mpsc::Receiver<RequestWithResponse>, game: Ar
                 GameResponse::success(game.get_game_state())
             }
             Request::NewGame(data) => {
-                let config = data
-                    .config_file
-                    .as_ref()
-                    .and_then(|path| GameConfig::load_from_file(&path).ok());
-                *game = Game::new(config, data.num_players);
-                GameResponse::success(game.get_game_state())
+                let sources = [
+                    data.config_file.is_some(),
+                    data.map.is_some(),
+                    data.generated_map.is_some(),
+                    data.scenario.is_some(),
+                ];
+                let config = if sources.iter().filter(|&&given| given).count() > 1 {
+                    Err("Give only one of map, generated_map, scenario and config_file".to_string())
+                } else if let Some(path) = &data.config_file {
+                    GameConfig::load_from_file(path).map(Some)
+                } else if let Some(scenario) = &data.scenario {
+                    let errors = scenario.validate();
+                    if errors.is_empty() {
+                        Ok(Some(scenario.as_ref().clone()))
+                    } else {
+                        Err(describe(&errors))
+                    }
+                } else if let Some(map) = &data.map {
+                    match MapDefinition::builtin(map) {
+                        Some(_) => Ok(Some(GameConfig::from_map(map))),
+                        None => Err(format!(
+                            "Unknown map {}, available: {}",
+                            map,
+                            MapDefinition::builtin_names().join(", ")
+                        )),
+                    }
+                } else if let Some(generator) = &data.generated_map {
+                    generate_map(generator).map(|map| Some(GameConfig::from_definition(map)))
+                } else {
+                    Ok(None)
+                };
+                let config = match data.distribution {
+                    Some(distribution) => config.map(|config| {
+                        let mut config =
+                            config.unwrap_or_else(|| GameConfig::from_map(CLASSIC_MAP));
+                        config.distribution = Some(distribution);
+                        Some(config)
+                    }),
+                    None => config,
+                };
+                let num_players = data
+                    .num_players
+                    .or(data.seats.as_ref().map(|seats| seats.len()));
+                match config.and_then(|config| {
+                    let seats = bots.create_seats(&data.seats.unwrap_or_default())?;
+                    Ok((Game::try_new(config, num_players)?, seats))
+                }) {
+                    Ok((new_game, new_seats)) => {
+                        *game = new_game;
+                        game.set_probability_table(prob_table.clone());
+                        let mut seats = bot_seats.lock().unwrap();
+                        seats.seats = new_seats;
+                        seats.generation += 1;
+                        seats.running = false;
+                        seats.thinking = None;
+                        GameResponse::success(game.get_game_state())
+                    }
+                    Err(e) => GameResponse::error(game.get_game_state(), e),
+                }
             }
             Request::GetGameState => GameResponse::success(game.get_game_state()),
+            Request::GetScenario => GameResponse {
+                scenario: Some(GameConfig::from_game(&game)),
+                ..GameResponse::success(game.get_game_state())
+            },
         };
+        // Bots move in the background, clients follow their moves through /game-state
+        let mut seats = bot_seats.lock().unwrap();
+        if response.error.is_none() && !seats.running && seats.bot_on_turn(&game) {
+            seats.running = true;
+            let (shared_game, bot_seats, generation) =
+                (shared_game.clone(), bot_seats.clone(), seats.generation);
+            tokio::task::spawn_blocking(move || play_bot_turns(shared_game, bot_seats, generation));
+        }
+        drop(seats);
+        drop(game);
         response_sender
             .send(response)
             .expect("Failed to send response");
@@ -337,10 +756,23 @@ async fn worker_task(mut receiver: mpsc::Receiver<RequestWithResponse>, game: Ar
 #[launch]
 async fn rocket() -> _ {
     let (sender, receiver) = mpsc::channel::<RequestWithResponse>(100);
-    let game = Arc::new(Mutex::new(Game::new(None, Some(6))));
+    let prob_table = match std::env::var(PROBABILITY_TABLE_ENV) {
+        Ok(path) => match ProbabilityTable::load_from_file(&path) {
+            Ok(table) => Arc::new(table),
+            Err(e) => {
+                eprintln!("Failed to load probability table, using the embedded one: {}", e);
+                ProbabilityTable::embedded()
+            }
+        },
+        Err(_) => ProbabilityTable::embedded(),
+    };
+    let mut game = Game::new(None, Some(6));
+    game.set_probability_table(prob_table.clone());
+    let game = Arc::new(Mutex::new(game));
+    let bots = Arc::new(BotRegistry::with_engines_file(ENGINES_FILE));
 
     //console_subscriber::init();
-    tokio::spawn(worker_task(receiver, game.clone()));
+    tokio::spawn(worker_task(receiver, game.clone(), bots.clone(), prob_table));
 
     let cors = CorsOptions::default()
         .allowed_origins(AllowedOrigins::all())
@@ -348,7 +780,7 @@ async fn rocket() -> _ {
         .expect("Error creating CORS middleware");
 
     rocket::build()
-        .manage(SharedState { sender })
+        .manage(SharedState { sender, bots })
         .mount(
             "/",
             routes![
@@ -361,6 +793,15 @@ async fn rocket() -> _ {
                 trade_cards,
                 advance_phase,
                 game_state,
+                scenario,
+                reinforcement_advice,
+                battle,
+                attack_path,
+                board_dot,
+                board_svg,
+                list_bots,
+                list_maps,
+                get_map,
                 new_game
             ],
         )
@@ -0,0 +1,276 @@
+// map.rs
+use crate::board::Board;
+use crate::continent::Continent;
+use crate::layout::{MapLayout, TerritoryLayout};
+use crate::territory::Territory;
+use crate::validation::describe;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// Name of the default built-in map. Built-in maps are usable wherever a map file path is
+// expected.
+pub const CLASSIC_MAP: &str = "classic";
+
+// Player counts a game can be started with
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 10;
+
+// Starting armies per player count for maps without their own table. They are meant for
+// the 42 territories of the classic map and grow with larger maps.
+const DEFAULT_STARTING_ARMIES: [(usize, u16); 9] = [
+    (2, 40),
+    (3, 35),
+    (4, 30),
+    (5, 25),
+    (6, 20),
+    (7, 20),
+    (8, 20),
+    (9, 20),
+    (10, 20),
+];
+const DEFAULT_TABLE_TERRITORIES: usize = 42;
+
+// Built-in maps: name, description and definition
+const BUILTIN_MAPS: &[(&str, &str, &str)] = &[
+    (
+        CLASSIC_MAP,
+        "The standard 42 territory world map",
+        include_str!("maps/classic.json"),
+    ),
+    (
+        "tiny",
+        "Seven territories on two continents, for tests and quick games",
+        include_str!("maps/tiny.json"),
+    ),
+    (
+        "ring",
+        "Four arcs around a central citadel that borders each of them",
+        include_str!("maps/ring.json"),
+    ),
+    (
+        "grid",
+        "A 6x4 grid of territories split into four quadrants",
+        include_str!("maps/grid.json"),
+    ),
+];
+
+// Catalog entry for a built-in map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapSummary {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub territories: usize,
+    pub continents: usize,
+}
+
+// Board-only data: territories, adjacency and continents, without any players
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapDefinition {
+    pub name: String,
+    pub territories: Vec<TerritoryConfig>,
+    pub continents: Vec<ContinentConfig>,
+    // Armies each player starts with by number of players, e.g. {"3": 35, "4": 30}
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub starting_armies: BTreeMap<usize, u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<MapLayout>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerritoryConfig {
+    pub name: String,
+    pub continent: String,
+    pub adjacent_territories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<TerritoryLayout>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinentConfig {
+    pub name: String,
+    pub bonus_armies: u16,
+    pub territories: Vec<String>,
+}
+
+impl MapDefinition {
+    // The standard 42 territory world map
+    pub fn classic() -> Self {
+        Self::builtin(CLASSIC_MAP).expect("The classic map is built in")
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN_MAPS
+            .iter()
+            .find(|(builtin, _, _)| *builtin == name)
+            .map(|(name, _, data)| {
+                serde_json::from_str(data)
+                    .unwrap_or_else(|e| panic!("Unable to parse built-in map {}: {}", name, e))
+            })
+    }
+
+    pub fn builtin_names() -> Vec<&'static str> {
+        BUILTIN_MAPS.iter().map(|(name, _, _)| *name).collect()
+    }
+
+    pub fn catalog() -> Vec<MapSummary> {
+        BUILTIN_MAPS
+            .iter()
+            .map(|(name, description, _)| {
+                let map = Self::builtin(name).expect("Catalog entries are built in");
+                MapSummary {
+                    name: name.to_string(),
+                    title: map.name,
+                    description: description.to_string(),
+                    territories: map.territories.len(),
+                    continents: map.continents.len(),
+                }
+            })
+            .collect()
+    }
+
+    // Loads and validates a map file
+    pub fn load_from_file(filename: &str) -> Result<Self, String> {
+        let map = Self::read_from_file(filename)?;
+        let errors = map.validate();
+        if !errors.is_empty() {
+            return Err(format!("{}: {}", filename, describe(&errors)));
+        }
+        Ok(map)
+    }
+
+    // Parses a map file without validating it, .map files are read as Domination maps
+    pub fn read_from_file(filename: &str) -> Result<Self, String> {
+        let data = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let path = Path::new(filename);
+        if is_domination_file(path) {
+            let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+            Self::from_domination(&data, &stem).map_err(|e| format!("{}: {}", filename, e))
+        } else {
+            serde_json::from_str(&data).map_err(|e| format!("{}: {}", filename, e))
+        }
+    }
+
+    // Writes JSON, or the Domination format for .map files
+    pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
+        let data = if is_domination_file(Path::new(filename)) {
+            self.to_domination()?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())?
+        };
+        std::fs::write(filename, data).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    // A built-in map by name, otherwise a map file. Relative paths are resolved against
+    // base_dir when one is given, e.g. the directory of the scenario referencing the map.
+    pub fn resolve(reference: &str, base_dir: Option<&Path>) -> Result<Self, String> {
+        if let Some(map) = Self::builtin(reference) {
+            return Ok(map);
+        }
+        let path = match base_dir {
+            Some(dir) if Path::new(reference).is_relative() => dir.join(reference),
+            _ => Path::new(reference).to_path_buf(),
+        };
+        Self::load_from_file(&path.to_string_lossy())
+    }
+
+    // Armies every player starts with when the territories are dealt, from the map's table
+    // or the default one. Fails when the player count isn't supported or the armies don't
+    // cover the territories each player is dealt.
+    pub fn starting_armies_for(&self, num_players: usize) -> Result<u16, String> {
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&num_players) {
+            return Err(format!(
+                "Games have {} to {} players, not {}",
+                MIN_PLAYERS, MAX_PLAYERS, num_players
+            ));
+        }
+        let territories = self.territories.len();
+        if num_players > territories {
+            return Err(format!(
+                "{} players don't fit on {} territories",
+                num_players, territories
+            ));
+        }
+        let armies = if self.starting_armies.is_empty() {
+            let (_, armies) = DEFAULT_STARTING_ARMIES
+                .iter()
+                .find(|(players, _)| *players == num_players)
+                .expect("The default table covers every player count");
+            let scale = territories.max(DEFAULT_TABLE_TERRITORIES);
+            (*armies as usize * scale).div_ceil(DEFAULT_TABLE_TERRITORIES) as u16
+        } else {
+            *self.starting_armies.get(&num_players).ok_or(format!(
+                "{} has no starting armies for {} players",
+                self.name, num_players
+            ))?
+        };
+        let dealt = territories.div_ceil(num_players);
+        if (armies as usize) < dealt {
+            return Err(format!(
+                "{} starting armies don't cover the {} territories each of {} players can be dealt",
+                armies, dealt, num_players
+            ));
+        }
+        Ok(armies)
+    }
+
+    // Names are sorted so the same board always gives the same definition
+    pub fn from_board(board: &Board, name: &str) -> Self {
+        let mut territories: Vec<TerritoryConfig> = board
+            .territories
+            .values()
+            .map(|territory| TerritoryConfig {
+                name: territory.name.clone(),
+                continent: territory.continent.clone(),
+                adjacent_territories: territory.adjacent_territories.iter().cloned().sorted().collect(),
+                layout: territory.layout.clone(),
+            })
+            .collect();
+        territories.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut continents: Vec<ContinentConfig> = board
+            .continents
+            .values()
+            .map(|continent| ContinentConfig {
+                name: continent.name.clone(),
+                bonus_armies: continent.bonus_armies,
+                territories: continent.territories.iter().cloned().sorted().collect(),
+            })
+            .collect();
+        continents.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            name: name.to_string(),
+            territories,
+            continents,
+            starting_armies: BTreeMap::new(),
+            layout: board.layout.clone(),
+        }
+    }
+
+    pub fn to_board(&self) -> Board {
+        let mut board = Board::new();
+        board.layout = self.layout.clone();
+        for continent_config in &self.continents {
+            let mut continent = Continent::new(&continent_config.name, continent_config.bonus_armies);
+            for territory_name in &continent_config.territories {
+                continent.add_territory(territory_name);
+            }
+            board.add_continent(continent);
+        }
+
+        for territory_config in &self.territories {
+            let mut territory = Territory::new(&territory_config.name, &territory_config.continent);
+            for adjacent in &territory_config.adjacent_territories {
+                territory.add_adjacent(adjacent);
+            }
+            territory.layout = territory_config.layout.clone();
+            board.add_territory(territory);
+        }
+        board
+    }
+}
+
+fn is_domination_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("map"))
+}
@@ -0,0 +1,247 @@
+// map_analysis.rs
+use crate::bot::{BotRegistry, Seat};
+use crate::game::Game;
+use crate::game_config::GameConfig;
+use crate::map::MapDefinition;
+use crate::map_generator::continent_bonus;
+use crate::simulation::play_game_observed;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfPlayConfig {
+    // One bot per seat
+    pub bots: Vec<String>,
+    pub games: usize,
+    pub seed: u64,
+    pub max_rounds: usize,
+}
+
+// How a continent fared in self-play
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinentSelfPlay {
+    // Share of turns that started with the continent in a single player's hands
+    pub hold_rate: f64,
+    // Games in which somebody held it at the start of a turn. Only the first player to
+    // hold it counts, by the end of a game the winner holds every continent.
+    pub holders: usize,
+    // Share of those games won by the first holder
+    pub holder_win_rate: f64,
+    // holder_win_rate relative to the win rate of a random seat, above 1 when holding the
+    // continent goes together with winning
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinentAnalysis {
+    pub name: String,
+    pub bonus_armies: u16,
+    pub territories: usize,
+    // Territories of the continent that border another continent and have to be defended
+    pub border_territories: usize,
+    // Borders crossing into the continent from outside
+    pub entry_points: usize,
+    pub neighboring_continents: Vec<String>,
+    // Foreign territories next to the continent per border territory
+    pub neighbor_pressure: f64,
+    // Bonus from size and border territories alone
+    pub structural_bonus: u16,
+    pub self_play: Option<ContinentSelfPlay>,
+    // Self-play estimate when there is one, the structural bonus otherwise
+    pub suggested_bonus: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapAnalysis {
+    pub map: String,
+    pub territories: usize,
+    pub continents: Vec<ContinentAnalysis>,
+    pub games: usize,
+    pub draws: usize,
+}
+
+// Board structure only
+pub fn analyze_map(map: &MapDefinition) -> MapAnalysis {
+    let continent_of: HashMap<&str, &str> = map
+        .territories
+        .iter()
+        .map(|t| (t.name.as_str(), t.continent.as_str()))
+        .collect();
+
+    let continents = map
+        .continents
+        .iter()
+        .map(|continent| {
+            let members: Vec<_> = map
+                .territories
+                .iter()
+                .filter(|t| t.continent == continent.name)
+                .collect();
+            let mut border_territories = 0;
+            let mut entry_points = 0;
+            let mut foreign = HashSet::new();
+            let mut neighboring_continents = BTreeSet::new();
+            for territory in &members {
+                let outside: Vec<&String> = territory
+                    .adjacent_territories
+                    .iter()
+                    .filter(|adjacent| {
+                        continent_of.get(adjacent.as_str()) != Some(&continent.name.as_str())
+                    })
+                    .collect();
+                if !outside.is_empty() {
+                    border_territories += 1;
+                }
+                entry_points += outside.len();
+                for adjacent in outside {
+                    foreign.insert(adjacent.as_str());
+                    if let Some(other) = continent_of.get(adjacent.as_str()) {
+                        neighboring_continents.insert(other.to_string());
+                    }
+                }
+            }
+
+            let structural_bonus = continent_bonus(members.len(), border_territories);
+            ContinentAnalysis {
+                name: continent.name.clone(),
+                bonus_armies: continent.bonus_armies,
+                territories: members.len(),
+                border_territories,
+                entry_points,
+                neighboring_continents: neighboring_continents.into_iter().collect(),
+                neighbor_pressure: foreign.len() as f64 / border_territories.max(1) as f64,
+                structural_bonus,
+                self_play: None,
+                suggested_bonus: structural_bonus,
+            }
+        })
+        .collect();
+
+    MapAnalysis {
+        map: map.name.clone(),
+        territories: map.territories.len(),
+        continents,
+        games: 0,
+        draws: 0,
+    }
+}
+
+// Continents held at the start of each turn and who held them first, per game
+#[derive(Default)]
+struct Holdings {
+    turns: usize,
+    held_turns: HashMap<String, usize>,
+    first_holder: HashMap<String, usize>,
+}
+
+// Board structure plus bot self-play on the map. Bonuses are suggested so that holding
+// any continent is worth about as much: a continent whose holders win more often than the
+// average continent's holders gets a lower bonus and vice versa.
+pub fn analyze_map_with_self_play(
+    map: &MapDefinition,
+    config: &SelfPlayConfig,
+    registry: &BotRegistry,
+) -> Result<MapAnalysis, String> {
+    let num_players = config.bots.len();
+    map.starting_armies_for(num_players)?;
+    let seats: Vec<Seat> = config
+        .bots
+        .iter()
+        .map(|name| Seat::Bot(name.clone()))
+        .collect();
+    registry.create_seats(&seats)?;
+
+    let scenario = GameConfig::from_definition(map.clone());
+    let games: Vec<(Option<usize>, Holdings)> = (0..config.games)
+        .into_par_iter()
+        .map(|index| {
+            // Rotate the seats so no continent profits from a seat advantage
+            let mut bots: Vec<_> = (0..num_players)
+                .map(|seat| {
+                    let name = &config.bots[(seat + index) % num_players];
+                    registry.create(name).expect("Bot was validated")
+                })
+                .collect();
+            let seed = config.seed.wrapping_add(index as u64);
+            let mut game = Game::new_seeded(Some(scenario.clone()), Some(num_players), seed);
+            let mut holdings = Holdings::default();
+            let result = play_game_observed(&mut game, &mut bots, config.max_rounds, |game| {
+                holdings.turns += 1;
+                for continent in game.board.continents.values() {
+                    let holder = game.players.iter().find(|p| {
+                        continent
+                            .territories
+                            .iter()
+                            .all(|t| p.territories.contains(t))
+                    });
+                    if let Some(holder) = holder {
+                        *holdings
+                            .held_turns
+                            .entry(continent.name.clone())
+                            .or_default() += 1;
+                        holdings
+                            .first_holder
+                            .entry(continent.name.clone())
+                            .or_insert(holder.id);
+                    }
+                }
+            });
+            (result.winner, holdings)
+        })
+        .collect();
+
+    let mut analysis = analyze_map(map);
+    analysis.games = games.len();
+    analysis.draws = games.iter().filter(|(winner, _)| winner.is_none()).count();
+    let total_turns: usize = games.iter().map(|(_, h)| h.turns).sum();
+    let baseline = 1.0 / num_players as f64;
+
+    for continent in &mut analysis.continents {
+        let held_turns: usize = games
+            .iter()
+            .map(|(_, h)| h.held_turns.get(&continent.name).copied().unwrap_or(0))
+            .sum();
+        let mut holders = 0;
+        let mut holder_wins = 0;
+        for (winner, h) in &games {
+            if let Some(holder) = h.first_holder.get(&continent.name) {
+                holders += 1;
+                if *winner == Some(*holder) {
+                    holder_wins += 1;
+                }
+            }
+        }
+        let holder_win_rate = holder_wins as f64 / holders.max(1) as f64;
+        continent.self_play = Some(ContinentSelfPlay {
+            hold_rate: held_turns as f64 / total_turns.max(1) as f64,
+            holders,
+            holder_win_rate,
+            value: holder_win_rate / baseline,
+        });
+    }
+
+    // Continents nobody held tell nothing about their value and keep the structural bonus
+    let values: Vec<f64> = analysis
+        .continents
+        .iter()
+        .filter_map(|c| c.self_play.as_ref())
+        .filter(|s| s.holders > 0 && s.value > 0.0)
+        .map(|s| s.value)
+        .collect();
+    if !values.is_empty() {
+        let average = values.iter().sum::<f64>() / values.len() as f64;
+        for continent in &mut analysis.continents {
+            let Some(self_play) = &continent.self_play else {
+                continue;
+            };
+            if self_play.holders > 0 && self_play.value > 0.0 {
+                // Limited to halving or doubling, a handful of games is noisy
+                let factor = (average / self_play.value).clamp(0.5, 2.0);
+                let scaled = continent.bonus_armies.max(1) as f64 * factor;
+                continent.suggested_bonus = scaled.round().max(1.0) as u16;
+            }
+        }
+    }
+    Ok(analysis)
+}
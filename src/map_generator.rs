@@ -0,0 +1,256 @@
+// map_generator.rs
+use crate::layout::{MapLayout, TerritoryLayout};
+use crate::map::{ContinentConfig, MapDefinition, TerritoryConfig};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+pub const MAX_GENERATED_TERRITORIES: usize = 300;
+
+// Width and height of the layout, the unit square is scaled up to it
+const LAYOUT_SIZE: f64 = 1000.0;
+
+// Candidates tried for every new territory, the one furthest from the others is kept
+const PLACEMENT_CANDIDATES: usize = 20;
+
+const SYLLABLES: &[&str] = &[
+    "ar", "bel", "cor", "dan", "el", "fen", "gar", "hal", "is", "kar", "lor", "mar", "nor", "or",
+    "pel", "quin", "ros", "sal", "tor", "ul", "val", "wen", "yr", "zan",
+];
+const CONTINENT_SUFFIXES: &[&str] = &["Reach", "Marches", "Expanse", "Highlands", "Basin", "Coast"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorConfig {
+    #[serde(default = "default_territories")]
+    pub territories: usize,
+    #[serde(default = "default_continents")]
+    pub continents: usize,
+    // A random seed is picked when none is given, the map name records it
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn default_territories() -> usize {
+    42
+}
+
+fn default_continents() -> usize {
+    6
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            territories: default_territories(),
+            continents: default_continents(),
+            seed: None,
+        }
+    }
+}
+
+// Builds a board from random points in the unit square. Two territories border when no
+// other point lies in the circle that has them as diameter (the Gabriel graph), which keeps
+// the graph planar and, as it contains the minimum spanning tree, connected. Continents grow
+// outwards from spread out seeds, one territory at a time each.
+pub fn generate_map(config: &GeneratorConfig) -> Result<MapDefinition, String> {
+    if !(2..=MAX_GENERATED_TERRITORIES).contains(&config.territories) {
+        return Err(format!(
+            "Generated maps have 2 to {} territories",
+            MAX_GENERATED_TERRITORIES
+        ));
+    }
+    if config.continents == 0 || config.continents > config.territories {
+        return Err("Every continent needs at least one territory".to_string());
+    }
+
+    let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let points = place_points(config.territories, &mut rng);
+    let adjacency = gabriel_graph(&points);
+    let continent_of = grow_continents(&points, &adjacency, config.continents, &mut rng);
+
+    let mut used_names = HashSet::new();
+    let territory_names: Vec<String> = (0..config.territories)
+        .map(|_| unique_name(&mut rng, &mut used_names, None))
+        .collect();
+    let continent_names: Vec<String> = (0..config.continents)
+        .map(|_| {
+            let suffix = CONTINENT_SUFFIXES.choose(&mut rng).copied();
+            unique_name(&mut rng, &mut used_names, suffix)
+        })
+        .collect();
+
+    let mut continents = vec![];
+    let mut territories = vec![];
+    for (continent, continent_name) in continent_names.iter().enumerate() {
+        let members: Vec<usize> = (0..config.territories)
+            .filter(|&t| continent_of[t] == continent)
+            .collect();
+        let borders = members
+            .iter()
+            .filter(|&&t| adjacency[t].iter().any(|&n| continent_of[n] != continent))
+            .count();
+        continents.push(ContinentConfig {
+            name: continent_name.clone(),
+            bonus_armies: continent_bonus(members.len(), borders),
+            territories: members
+                .iter()
+                .map(|&t| territory_names[t].clone())
+                .collect(),
+        });
+        for &t in &members {
+            territories.push(TerritoryConfig {
+                name: territory_names[t].clone(),
+                continent: continent_name.clone(),
+                adjacent_territories: adjacency[t]
+                    .iter()
+                    .map(|&n| territory_names[n].clone())
+                    .collect(),
+                layout: Some(TerritoryLayout::at(
+                    (points[t].0 * LAYOUT_SIZE).round(),
+                    (points[t].1 * LAYOUT_SIZE).round(),
+                )),
+            });
+        }
+    }
+
+    Ok(MapDefinition {
+        name: format!("Generated {}", seed),
+        territories,
+        continents,
+        starting_armies: BTreeMap::new(),
+        layout: Some(MapLayout {
+            width: Some(LAYOUT_SIZE),
+            height: Some(LAYOUT_SIZE),
+            ..MapLayout::default()
+        }),
+    })
+}
+
+// Bonus from the size of a continent and the number of territories it has to defend,
+// fitted to the classic map where it gives the official bonuses for all but Europe
+pub fn continent_bonus(territories: usize, border_territories: usize) -> u16 {
+    (0.4 * territories as f64 + 0.5 * border_territories as f64 - 0.5)
+        .round()
+        .max(1.0) as u16
+}
+
+// Best candidate sampling, spreads the points more evenly than uniform sampling
+fn place_points(count: usize, rng: &mut StdRng) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = vec![];
+    while points.len() < count {
+        let best = (0..PLACEMENT_CANDIDATES)
+            .map(|_| (rng.gen::<f64>(), rng.gen::<f64>()))
+            .map(|candidate| {
+                let nearest = points
+                    .iter()
+                    .map(|&point| distance_squared(candidate, point))
+                    .fold(f64::INFINITY, f64::min);
+                (candidate, nearest)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(candidate, _)| candidate)
+            .expect("At least one candidate");
+        points.push(best);
+    }
+    points
+}
+
+fn gabriel_graph(points: &[(f64, f64)]) -> Vec<Vec<usize>> {
+    let mut adjacency = vec![vec![]; points.len()];
+    for a in 0..points.len() {
+        for b in a + 1..points.len() {
+            let length = distance_squared(points[a], points[b]);
+            let blocked = (0..points.len()).any(|c| {
+                c != a
+                    && c != b
+                    && distance_squared(points[a], points[c])
+                        + distance_squared(points[b], points[c])
+                        <= length
+            });
+            if !blocked {
+                adjacency[a].push(b);
+                adjacency[b].push(a);
+            }
+        }
+    }
+    adjacency
+}
+
+// Continent index of every territory
+fn grow_continents(
+    points: &[(f64, f64)],
+    adjacency: &[Vec<usize>],
+    continents: usize,
+    rng: &mut StdRng,
+) -> Vec<usize> {
+    const UNASSIGNED: usize = usize::MAX;
+    let mut continent_of = vec![UNASSIGNED; points.len()];
+
+    // Farthest point sampling for the seeds
+    let mut seeds = vec![rng.gen_range(0..points.len())];
+    while seeds.len() < continents {
+        let next = (0..points.len())
+            .filter(|t| !seeds.contains(t))
+            .max_by(|&a, &b| {
+                let nearest = |t: usize| {
+                    seeds
+                        .iter()
+                        .map(|&s| distance_squared(points[t], points[s]))
+                        .fold(f64::INFINITY, f64::min)
+                };
+                nearest(a).total_cmp(&nearest(b))
+            })
+            .expect("More territories than continents");
+        seeds.push(next);
+    }
+    for (continent, &seed) in seeds.iter().enumerate() {
+        continent_of[seed] = continent;
+    }
+
+    let mut assigned = continents;
+    while assigned < points.len() {
+        for continent in 0..continents {
+            let frontier: Vec<usize> = (0..points.len())
+                .filter(|&t| {
+                    continent_of[t] == UNASSIGNED
+                        && adjacency[t].iter().any(|&n| continent_of[n] == continent)
+                })
+                .collect();
+            if let Some(&territory) = frontier.choose(rng) {
+                continent_of[territory] = continent;
+                assigned += 1;
+            }
+        }
+    }
+    continent_of
+}
+
+fn unique_name(rng: &mut StdRng, used: &mut HashSet<String>, suffix: Option<&str>) -> String {
+    for attempt in 0.. {
+        let syllables = rng.gen_range(2..=3);
+        let mut name: String = (0..syllables)
+            .map(|_| *SYLLABLES.choose(rng).expect("Syllables are not empty"))
+            .collect();
+        if let Some(first) = name.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        // Running out of fresh combinations on large maps, number them instead
+        if attempt > 50 {
+            name = format!("{} {}", name, attempt);
+        }
+        if let Some(suffix) = suffix {
+            name = format!("{} {}", name, suffix);
+        }
+        if used.insert(name.clone()) {
+            return name;
+        }
+    }
+    unreachable!("Names with a running number are unique")
+}
+
+fn distance_squared(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
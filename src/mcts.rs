@@ -0,0 +1,384 @@
+// mcts.rs
+use crate::bot::{default_action, Bot};
+use crate::card::Card;
+use crate::game::{Action, Game, GameState};
+use crate::turn_phase::TurnPhase;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+// Exploration constant for UCT
+const EXPLORATION: f64 = 1.4;
+// Number of actions a rollout plays before the position is scored heuristically
+const ROLLOUT_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn time_budget(&self) -> Duration {
+        match self {
+            Difficulty::Easy => Duration::from_millis(50),
+            Difficulty::Medium => Duration::from_millis(250),
+            Difficulty::Hard => Duration::from_millis(1000),
+        }
+    }
+}
+
+// Monte Carlo tree search over cloned games. Each rayon thread grows its own tree on a
+// separately dealt deck and the root visit counts are merged to pick the move.
+pub struct MctsBot {
+    name: String,
+    time_budget: Duration,
+}
+
+impl MctsBot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        let name = match difficulty {
+            Difficulty::Easy => "mcts_easy",
+            Difficulty::Medium => "mcts_medium",
+            Difficulty::Hard => "mcts_hard",
+        };
+        Self::with_time_budget(name, difficulty.time_budget())
+    }
+
+    pub fn with_time_budget(name: &str, time_budget: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            time_budget,
+        }
+    }
+}
+
+impl Bot for MctsBot {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Action {
+        // Nothing to search with a single action or none at all
+        if state.possible_actions.len() <= 1 {
+            return default_action(state);
+        }
+
+        let deadline = Instant::now() + self.time_budget;
+        let root_edges: Vec<Vec<(Action, u32, f64)>> = (0..rayon::current_num_threads())
+            .into_par_iter()
+            .map(|_| search(state, deadline))
+            .collect();
+
+        let mut totals: Vec<(Action, u32, f64)> = vec![];
+        for (action, visits, reward) in root_edges.into_iter().flatten() {
+            match totals.iter_mut().find(|(a, _, _)| *a == action) {
+                Some((_, total_visits, total_reward)) => {
+                    *total_visits += visits;
+                    *total_reward += reward;
+                }
+                None => totals.push((action, visits, reward)),
+            }
+        }
+
+        // Most visited action wins, ties go to the better mean reward
+        totals
+            .into_iter()
+            .max_by(|(_, visits_a, reward_a), (_, visits_b, reward_b)| {
+                visits_a.cmp(visits_b).then_with(|| {
+                    let mean_a = reward_a / (*visits_a).max(1) as f64;
+                    let mean_b = reward_b / (*visits_b).max(1) as f64;
+                    mean_a.total_cmp(&mean_b)
+                })
+            })
+            .map(|(action, _, _)| action)
+            .unwrap_or_else(|| default_action(state))
+    }
+}
+
+struct Node {
+    visits: u32,
+    edges: Option<Vec<Edge>>,
+}
+
+struct Edge {
+    action: Action,
+    visits: u32,
+    // Summed rewards, one entry per player
+    rewards: Vec<f64>,
+    // Chance node, one child per distinct outcome of the dice or the card draw
+    outcomes: HashMap<u64, Node>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Self {
+            visits: 0,
+            edges: None,
+        }
+    }
+}
+
+// Grow a tree until the deadline and report visits and summed reward of every root action
+fn search(state: &GameState, deadline: Instant) -> Vec<(Action, u32, f64)> {
+    let root_game = Game::from_game_state(state);
+    let mut root = Node::new();
+    let mut rng = thread_rng();
+
+    loop {
+        // Clones share the rng of the root game, so every iteration needs its own dice
+        let mut game = root_game.clone();
+        game.reseed(rng.gen());
+        iterate(&mut root, &mut game);
+        if Instant::now() >= deadline {
+            break;
+        }
+    }
+
+    root.edges
+        .unwrap_or_default()
+        .into_iter()
+        .map(|edge| (edge.action, edge.visits, edge.rewards[state.current_turn]))
+        .collect()
+}
+
+fn iterate(node: &mut Node, game: &mut Game) -> Vec<f64> {
+    if game.turn_phase == TurnPhase::GameOver {
+        return evaluate(game);
+    }
+
+    let parent_visits = node.visits.max(1) as f64;
+    let edges = node.edges.get_or_insert_with(|| {
+        candidate_actions(game)
+            .into_iter()
+            .map(|action| Edge {
+                action,
+                visits: 0,
+                rewards: vec![0.0; game.players.len()],
+                outcomes: HashMap::new(),
+            })
+            .collect()
+    });
+    if edges.is_empty() {
+        return evaluate(game);
+    }
+
+    let player_id = game.current_turn;
+    let edge_index = (0..edges.len())
+        .max_by(|&a, &b| {
+            let score = |edge: &Edge| {
+                if edge.visits == 0 {
+                    return f64::INFINITY;
+                }
+                let mean = edge.rewards[player_id] / edge.visits as f64;
+                mean + EXPLORATION * (parent_visits.ln() / edge.visits as f64).sqrt()
+            };
+            score(&edges[a]).total_cmp(&score(&edges[b]))
+        })
+        .expect("Edges are not empty");
+
+    let edge = &mut edges[edge_index];
+    let rewards = if game.apply_action(player_id, &edge.action).is_err() {
+        evaluate(game)
+    } else {
+        let child = edge
+            .outcomes
+            .entry(outcome_key(game, &edge.action))
+            .or_insert_with(Node::new);
+        if child.visits == 0 {
+            child.visits += 1;
+            rollout(game)
+        } else {
+            iterate(child, game)
+        }
+    };
+
+    edge.visits += 1;
+    for (total, reward) in edge.rewards.iter_mut().zip(&rewards) {
+        *total += reward;
+    }
+    node.visits += 1;
+    rewards
+}
+
+// Prune the action list to moves worth searching: all-in reinforcements on borders, attacks
+// from the bigger stack, moving the minimum or maximum after a conquest and fortifying from
+// the interior only
+fn candidate_actions(game: &Game) -> Vec<Action> {
+    let player = &game.players[game.current_turn];
+    let is_border = |territory: &str| {
+        game.board
+            .get_territory(territory)
+            .map(|t| {
+                t.adjacent_territories
+                    .iter()
+                    .any(|adjacent| !player.territories.contains(adjacent))
+            })
+            .unwrap_or(false)
+    };
+
+    game.get_possible_actions()
+        .into_iter()
+        .flat_map(|action| match action {
+            Action::Reinforce { ref territory, .. } if !is_border(territory) => vec![],
+            Action::Attack { ref from, ref to, .. }
+                if player.get_armies(from) <= defender_armies(game, to) + 1 =>
+            {
+                vec![]
+            }
+            Action::Fortify { ref from, ref to, .. } if is_border(from) || !is_border(to) => {
+                vec![]
+            }
+            Action::MoveArmies {
+                ref from,
+                ref to,
+                max_armies,
+                min_armies,
+            } if min_armies < max_armies => vec![
+                Action::MoveArmies {
+                    from: from.clone(),
+                    to: to.clone(),
+                    max_armies: min_armies,
+                    min_armies,
+                },
+                action,
+            ],
+            action => vec![action],
+        })
+        .collect()
+}
+
+// Outcomes of the same action share a child only if the dice or the card draw came out the same
+fn outcome_key(game: &Game, action: &Action) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match action {
+        Action::Attack { from, to, .. } => {
+            for territory in [from, to] {
+                let owner = game
+                    .players
+                    .iter()
+                    .find(|p| p.territories.contains(territory));
+                owner.map(|p| p.id).hash(&mut hasher);
+                owner.map(|p| p.get_armies(territory)).hash(&mut hasher);
+            }
+        }
+        _ => {
+            for player in &game.players {
+                for Card { territory, kind } in &player.cards {
+                    territory.hash(&mut hasher);
+                    kind.hash(&mut hasher);
+                }
+            }
+        }
+    }
+    hasher.finish()
+}
+
+// Play cheap heuristic moves until the game ends or the rollout limit is hit
+fn rollout(game: &mut Game) -> Vec<f64> {
+    let mut rng = thread_rng();
+    for _ in 0..ROLLOUT_LIMIT {
+        if game.turn_phase == TurnPhase::GameOver {
+            break;
+        }
+
+        let player_id = game.current_turn;
+        if game.turn_phase == TurnPhase::Fortify {
+            // Listing fortifications is expensive and rarely matters for the outcome
+            game.advance_phase();
+            continue;
+        }
+
+        let player = &game.players[player_id];
+        let actions = game.get_possible_actions();
+        let chosen = match game.turn_phase {
+            TurnPhase::Reinforce => actions
+                .iter()
+                .find(|action| matches!(action, Action::TradeCards { .. }))
+                .or_else(|| actions.choose(&mut rng)),
+            TurnPhase::Attack => {
+                let favourable: Vec<&Action> = actions
+                    .iter()
+                    .filter(|action| match action {
+                        Action::Attack { from, to, .. } => {
+                            player.get_armies(from) > defender_armies(game, to) + 1
+                        }
+                        _ => false,
+                    })
+                    .collect();
+                favourable
+                    .choose(&mut rng)
+                    .copied()
+                    .or_else(|| actions.iter().find(|a| matches!(a, Action::EndPhase)))
+            }
+            _ => actions.first(),
+        };
+
+        let Some(action) = chosen.cloned() else {
+            break;
+        };
+        if game.apply_action(player_id, &action).is_err() {
+            break;
+        }
+    }
+    evaluate(game)
+}
+
+fn defender_armies(game: &Game, territory: &str) -> u16 {
+    game.players
+        .iter()
+        .find(|p| p.territories.contains(territory))
+        .map(|p| p.get_armies(territory))
+        .unwrap_or(0)
+}
+
+// Share of the board for every player, averaged over territories, armies and income
+fn evaluate(game: &Game) -> Vec<f64> {
+    let total_territories = game.board.territories.len() as f64;
+    if let Some(winner) = game
+        .players
+        .iter()
+        .position(|p| p.territories.len() as f64 == total_territories)
+    {
+        return (0..game.players.len())
+            .map(|id| if id == winner { 1.0 } else { 0.0 })
+            .collect();
+    }
+
+    let armies: Vec<f64> = game
+        .players
+        .iter()
+        .map(|p| p.armies.values().map(|&a| a as f64).sum())
+        .collect();
+    let income: Vec<f64> = game
+        .players
+        .iter()
+        .map(|p| {
+            if p.territories.is_empty() {
+                0.0
+            } else {
+                game.calculate_reinforcements(p.id) as f64
+            }
+        })
+        .collect();
+    let total_armies: f64 = armies.iter().sum::<f64>().max(1.0);
+    let total_income: f64 = income.iter().sum::<f64>().max(1.0);
+
+    game.players
+        .iter()
+        .enumerate()
+        .map(|(id, p)| {
+            (p.territories.len() as f64 / total_territories
+                + armies[id] / total_armies
+                + income[id] / total_income)
+                / 3.0
+        })
+        .collect()
+}
@@ -0,0 +1,191 @@
+// probability_table.rs
+use crate::battle::conquer_probabilities;
+use std::sync::Arc;
+
+// Dense conquer probability table. The file layout, all little endian:
+//   magic     4 bytes  "RCPT"
+//   version   u16      FORMAT_VERSION
+//   flags     u16      bit 0 set: values are rounded to u16 units of 1/ROUNDED_SCALE,
+//                      otherwise they are f64
+//   max_attacker u16, max_defender u16
+//   values    row-major, (max_attacker + 1) rows of (max_defender + 1) values, so the
+//             probability for (a, d) is at a * (max_defender + 1) + d
+const MAGIC: &[u8; 4] = b"RCPT";
+const FORMAT_VERSION: u16 = 1;
+const FLAG_ROUNDED: u16 = 1;
+const HEADER_LEN: usize = 12;
+pub const ROUNDED_SCALE: f64 = 10_000.0;
+
+// Table shipped with the binary, written by precompute_conquest_probabilities
+static EMBEDDED_TABLE: &[u8] = include_bytes!("../conquer_probabilities.bin");
+
+lazy_static::lazy_static! {
+    static ref EMBEDDED: Arc<ProbabilityTable> = Arc::new(
+        ProbabilityTable::from_bytes(EMBEDDED_TABLE).expect("Embedded probability table is invalid")
+    );
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Values {
+    Rounded(Vec<u16>),
+    Exact(Vec<f64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbabilityTable {
+    max_attacker: u16,
+    max_defender: u16,
+    values: Values,
+}
+
+impl ProbabilityTable {
+    // Compute a table with the battle DP
+    pub fn compute(max_attacker: u16, max_defender: u16, rounded: bool) -> Self {
+        Self::from_rows(&conquer_probabilities(max_attacker, max_defender), rounded)
+    }
+
+    // Build from rows indexed [attacker][defender], all of the same length
+    pub fn from_rows(rows: &[Vec<f64>], rounded: bool) -> Self {
+        let max_attacker = rows.len().saturating_sub(1) as u16;
+        let max_defender = rows.first().map(|row| row.len()).unwrap_or(1).saturating_sub(1) as u16;
+        let flat = rows.iter().flatten().copied();
+        let values = if rounded {
+            Values::Rounded(flat.map(|p| (p * ROUNDED_SCALE).round() as u16).collect())
+        } else {
+            Values::Exact(flat.collect())
+        };
+        Self {
+            max_attacker,
+            max_defender,
+            values,
+        }
+    }
+
+    pub fn rows(&self) -> Vec<Vec<f64>> {
+        (0..=self.max_attacker)
+            .map(|a| {
+                (0..=self.max_defender)
+                    .map(|d| self.get(a, d).expect("Index lies inside the table"))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Shared instance of the table compiled into the binary
+    pub fn embedded() -> Arc<Self> {
+        EMBEDDED.clone()
+    }
+
+    pub fn load_from_file(filename: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        Self::from_bytes(&bytes).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
+        std::fs::write(filename, self.to_bytes()).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+            return Err("Not a conquer probability table".to_string());
+        }
+        let read_u16 = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let version = read_u16(4);
+        if version != FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported probability table version {}, expected {}",
+                version, FORMAT_VERSION
+            ));
+        }
+        let flags = read_u16(6);
+        let max_attacker = read_u16(8);
+        let max_defender = read_u16(10);
+
+        let count = (max_attacker as usize + 1) * (max_defender as usize + 1);
+        let data = &bytes[HEADER_LEN..];
+        let value_size = if flags & FLAG_ROUNDED != 0 { 2 } else { 8 };
+        if data.len() != count * value_size {
+            return Err(format!(
+                "Expected {} bytes of values for {}x{}, found {}",
+                count * value_size,
+                max_attacker,
+                max_defender,
+                data.len()
+            ));
+        }
+
+        let values = if flags & FLAG_ROUNDED != 0 {
+            Values::Rounded(
+                data.chunks_exact(2)
+                    .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                    .collect(),
+            )
+        } else {
+            Values::Exact(
+                data.chunks_exact(8)
+                    .map(|chunk| f64::from_le_bytes(chunk.try_into().expect("Chunk of 8 bytes")))
+                    .collect(),
+            )
+        };
+        Ok(Self {
+            max_attacker,
+            max_defender,
+            values,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.len() * 8);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        let flags = if self.is_rounded() { FLAG_ROUNDED } else { 0 };
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        bytes.extend_from_slice(&self.max_attacker.to_le_bytes());
+        bytes.extend_from_slice(&self.max_defender.to_le_bytes());
+        match &self.values {
+            Values::Rounded(values) => {
+                for value in values {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            Values::Exact(values) => {
+                for value in values {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn max_attacker(&self) -> u16 {
+        self.max_attacker
+    }
+
+    pub fn max_defender(&self) -> u16 {
+        self.max_defender
+    }
+
+    pub fn is_rounded(&self) -> bool {
+        matches!(self.values, Values::Rounded(_))
+    }
+
+    fn len(&self) -> usize {
+        match &self.values {
+            Values::Rounded(values) => values.len(),
+            Values::Exact(values) => values.len(),
+        }
+    }
+
+    // None when the pair lies outside the table
+    pub fn get(&self, attacker_armies: u16, defender_armies: u16) -> Option<f64> {
+        if attacker_armies > self.max_attacker || defender_armies > self.max_defender {
+            return None;
+        }
+        let index =
+            attacker_armies as usize * (self.max_defender as usize + 1) + defender_armies as usize;
+        Some(match &self.values {
+            Values::Rounded(values) => values[index] as f64 / ROUNDED_SCALE,
+            Values::Exact(values) => values[index],
+        })
+    }
+}
@@ -0,0 +1,310 @@
+// render.rs
+use crate::board::Board;
+use crate::player::Player;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+// Fill colors of the players, in player order. Unowned territories are white.
+const PLAYER_COLORS: [&str; 10] = [
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#d4b000", "#a65628", "#f781bf",
+    "#17becf", "#999999",
+];
+const UNOWNED_COLOR: &str = "#ffffff";
+
+// Where each territory is drawn
+type Positions<'a> = BTreeMap<&'a str, (f64, f64)>;
+// Continent name with the center and radius of its circle
+type Clusters<'a> = Vec<(&'a str, f64, f64, f64)>;
+
+// SVG drawing without a layout: territories sit on a circle per continent, continents on a grid
+const NODE_RADIUS: f64 = 20.0;
+const NODE_SPACING: f64 = 70.0;
+const CLUSTER_PADDING: f64 = 50.0;
+const LEGEND_HEIGHT: f64 = 30.0;
+
+pub fn player_color(player_id: usize) -> &'static str {
+    PLAYER_COLORS[player_id % PLAYER_COLORS.len()]
+}
+
+impl Board {
+    // Graphviz graph with one cluster per continent. Territories are colored by owner and
+    // labelled with their armies, render it with e.g. `dot -Tsvg` or `neato -Tpng`.
+    pub fn to_dot(&self, players: &[Player]) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "graph \"Board\" {{").unwrap();
+        writeln!(dot, "  node [shape=ellipse, style=filled, fontname=\"Helvetica\"];").unwrap();
+
+        for (continent, territories) in self.territories_by_continent() {
+            let bonus = self.get_continent(continent).map_or(0, |c| c.bonus_armies);
+            writeln!(dot, "  subgraph {} {{", quote(&format!("cluster_{}", continent))).unwrap();
+            writeln!(dot, "    label={};", quote(&format!("{} (+{})", continent, bonus))).unwrap();
+            writeln!(dot, "    style=rounded;").unwrap();
+            for territory in territories {
+                let (label, color) = match owner(players, territory) {
+                    Some(player) => (
+                        format!("{}\n{}", territory, player.get_armies(territory)),
+                        player_color(player.id),
+                    ),
+                    None => (territory.to_string(), UNOWNED_COLOR),
+                };
+                writeln!(
+                    dot,
+                    "    {} [label={}, fillcolor=\"{}\"];",
+                    quote(territory),
+                    quote(&label),
+                    color
+                )
+                .unwrap();
+            }
+            writeln!(dot, "  }}").unwrap();
+        }
+
+        for (from, to) in self.edges() {
+            writeln!(dot, "  {} -- {};", quote(from), quote(to)).unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    // Self-contained SVG of the board, without needing Graphviz. Maps whose territories all
+    // have a center in their layout are drawn there with their outlines, other maps get a
+    // circle of territories per continent. Edges between continents are dashed, the legend
+    // lists the players with their colors.
+    pub fn to_svg(&self, players: &[Player]) -> String {
+        let (positions, clusters, width, height) = match self.layout_positions() {
+            Some((positions, width, height)) => (positions, vec![], width, height),
+            None => self.cluster_positions(),
+        };
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" \
+             viewBox=\"0 0 {:.0} {:.0}\" font-family=\"Helvetica, Arial, sans-serif\">",
+            width,
+            height + LEGEND_HEIGHT,
+            width,
+            height + LEGEND_HEIGHT
+        )
+        .unwrap();
+        writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"#fafafa\"/>").unwrap();
+
+        // Legend
+        for (index, player) in players.iter().enumerate() {
+            let x = 10.0 + index as f64 * 120.0;
+            writeln!(
+                svg,
+                "<rect x=\"{:.0}\" y=\"8\" width=\"14\" height=\"14\" fill=\"{}\" stroke=\"#333\"/>\
+                 <text x=\"{:.0}\" y=\"20\" font-size=\"12\">{}</text>",
+                x,
+                player_color(player.id),
+                x + 20.0,
+                escape(&player.name)
+            )
+            .unwrap();
+        }
+        writeln!(svg, "<g transform=\"translate(0 {:.0})\">", LEGEND_HEIGHT).unwrap();
+
+        // Continents
+        for (continent, cx, cy, radius) in clusters {
+            let bonus = self.get_continent(continent).map_or(0, |c| c.bonus_armies);
+            writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"#eeeeee\" stroke=\"#bbbbbb\"/>\
+                 <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"14\" font-weight=\"bold\" \
+                 text-anchor=\"middle\">{} (+{})</text>",
+                cx,
+                cy,
+                radius + NODE_RADIUS + 15.0,
+                cx,
+                cy - radius - NODE_RADIUS - 20.0,
+                escape(continent),
+                bonus
+            )
+            .unwrap();
+        }
+
+        // Outlines from the layout, filled lightly with the owner's color
+        for territory in positions.keys() {
+            let Some(layout) = &self.territories[*territory].layout else {
+                continue;
+            };
+            let color = owner(players, territory).map_or(UNOWNED_COLOR, |p| player_color(p.id));
+            let style = format!("fill=\"{}\" fill-opacity=\"0.35\" stroke=\"#555\"", color);
+            if let Some(path) = &layout.path {
+                writeln!(svg, "<path d=\"{}\" {}/>", escape(path), style).unwrap();
+            } else if !layout.polygon.is_empty() {
+                let points: Vec<String> = layout
+                    .polygon
+                    .iter()
+                    .map(|point| format!("{:.1},{:.1}", point.x, point.y))
+                    .collect();
+                writeln!(svg, "<polygon points=\"{}\" {}/>", points.join(" "), style).unwrap();
+            }
+        }
+
+        // Edges below the territories
+        for (from, to) in self.edges() {
+            let (Some((x1, y1)), Some((x2, y2))) = (positions.get(from), positions.get(to)) else {
+                continue;
+            };
+            let same_continent = self.territories[from].continent == self.territories[to].continent;
+            writeln!(
+                svg,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#777\"{}/>",
+                x1,
+                y1,
+                x2,
+                y2,
+                if same_continent { "" } else { " stroke-dasharray=\"4 3\"" }
+            )
+            .unwrap();
+        }
+
+        // Territories
+        for (territory, (x, y)) in &positions {
+            let (lx, ly) = self.territories[*territory]
+                .layout
+                .as_ref()
+                .and_then(|layout| layout.label_offset)
+                .map_or((*x, y + NODE_RADIUS + 12.0), |offset| (x + offset.x, y + offset.y));
+            let (color, armies) = match owner(players, territory) {
+                Some(player) => (player_color(player.id), player.get_armies(territory).to_string()),
+                None => (UNOWNED_COLOR, String::new()),
+            };
+            writeln!(
+                svg,
+                "<g><title>{name}</title>\
+                 <circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{r:.0}\" fill=\"{color}\" stroke=\"#333\"/>\
+                 <text x=\"{x:.1}\" y=\"{ay:.1}\" font-size=\"13\" font-weight=\"bold\" \
+                 text-anchor=\"middle\">{armies}</text>\
+                 <text x=\"{lx:.1}\" y=\"{ly:.1}\" font-size=\"10\" text-anchor=\"middle\">{name}</text></g>",
+                name = escape(territory),
+                x = x,
+                y = y,
+                r = NODE_RADIUS,
+                color = color,
+                ay = y + 4.5,
+                armies = armies,
+                lx = lx,
+                ly = ly,
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</g>").unwrap();
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+
+    // Centers from the layout when every territory has one, with the size of the layout or
+    // of the area the centers cover
+    fn layout_positions(&self) -> Option<(Positions<'_>, f64, f64)> {
+        let mut positions = BTreeMap::new();
+        for territory in self.territories.values() {
+            let center = territory.layout.as_ref()?.center?;
+            positions.insert(territory.name.as_str(), (center.x, center.y));
+        }
+        let extent = |coordinate: fn(&(f64, f64)) -> f64| {
+            positions.values().map(coordinate).fold(0.0, f64::max) + CLUSTER_PADDING
+        };
+        let layout = self.layout.as_ref();
+        let width = layout.and_then(|l| l.width).unwrap_or_else(|| extent(|p| p.0));
+        let height = layout.and_then(|l| l.height).unwrap_or_else(|| extent(|p| p.1));
+        Some((positions, width, height))
+    }
+
+    // Territories on a circle per continent and continents on a grid
+    fn cluster_positions(&self) -> (Positions<'_>, Clusters<'_>, f64, f64) {
+        let by_continent = self.territories_by_continent();
+        let radii: Vec<f64> = by_continent
+            .values()
+            .map(|territories| cluster_radius(territories.len()))
+            .collect();
+        let cell = radii.iter().cloned().fold(0.0, f64::max) * 2.0 + CLUSTER_PADDING * 2.0;
+        let columns = (by_continent.len() as f64).sqrt().ceil().max(1.0) as usize;
+        let rows = by_continent.len().div_ceil(columns).max(1);
+
+        let mut clusters = vec![];
+        let mut positions = BTreeMap::new();
+        for (index, ((continent, territories), radius)) in by_continent.iter().zip(&radii).enumerate() {
+            let cx = cell * ((index % columns) as f64 + 0.5);
+            let cy = cell * ((index / columns) as f64 + 0.5);
+            clusters.push((*continent, cx, cy, *radius));
+            for (i, territory) in territories.iter().enumerate() {
+                let angle = 2.0 * PI * i as f64 / territories.len() as f64 - PI / 2.0;
+                let position = if territories.len() == 1 {
+                    (cx, cy)
+                } else {
+                    (cx + radius * angle.cos(), cy + radius * angle.sin())
+                };
+                positions.insert(*territory, position);
+            }
+        }
+        (positions, clusters, cell * columns as f64, cell * rows as f64)
+    }
+
+    // Territory names per continent, both sorted so the output is stable
+    fn territories_by_continent(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut clusters: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for territory in self.territories.values() {
+            clusters
+                .entry(&territory.continent)
+                .or_default()
+                .push(&territory.name);
+        }
+        for territories in clusters.values_mut() {
+            territories.sort();
+        }
+        clusters
+    }
+
+    // Every adjacency once, as sorted pairs
+    fn edges(&self) -> Vec<(&str, &str)> {
+        let mut edges: Vec<(&str, &str)> = self
+            .territories
+            .values()
+            .flat_map(|territory| {
+                territory
+                    .adjacent_territories
+                    .iter()
+                    .map(move |adjacent| {
+                        if territory.name < *adjacent {
+                            (territory.name.as_str(), adjacent.as_str())
+                        } else {
+                            (adjacent.as_str(), territory.name.as_str())
+                        }
+                    })
+            })
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+}
+
+fn owner<'a>(players: &'a [Player], territory: &str) -> Option<&'a Player> {
+    players.iter().find(|player| player.territories.contains(territory))
+}
+
+// Territories far enough apart on the circle that their labels don't overlap
+fn cluster_radius(territories: usize) -> f64 {
+    if territories <= 1 {
+        return 0.0;
+    }
+    (territories as f64 * NODE_SPACING / (2.0 * PI)).max(NODE_SPACING / 2.0)
+}
+
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,79 @@
+// simulation.rs
+use crate::bot::{default_action, Bot};
+use crate::game::Game;
+use crate::turn_phase::TurnPhase;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameResult {
+    pub winner: Option<usize>,
+    pub rounds: usize,
+    pub actions: usize,
+    // Player ids in the order they were defeated
+    pub elimination_order: Vec<usize>,
+}
+
+impl GameResult {
+    // 1 for the winner, then counting up in reverse elimination order. Players still
+    // alive in a drawn game share the best place.
+    pub fn placement(&self, player_id: usize, num_players: usize) -> usize {
+        match self
+            .elimination_order
+            .iter()
+            .position(|&defeated| defeated == player_id)
+        {
+            Some(index) => num_players - index,
+            None => 1,
+        }
+    }
+}
+
+// Play a game with a bot on every seat until somebody wins. Games still running after
+// max_rounds end as a draw.
+pub fn play_game(game: &mut Game, bots: &mut [Box<dyn Bot>], max_rounds: usize) -> GameResult {
+    play_game_observed(game, bots, max_rounds, |_| {})
+}
+
+// play_game that calls on_turn_start with the game at the start of every turn
+pub fn play_game_observed(
+    game: &mut Game,
+    bots: &mut [Box<dyn Bot>],
+    max_rounds: usize,
+    mut on_turn_start: impl FnMut(&Game),
+) -> GameResult {
+    let mut actions = 0;
+    let mut last_turn = None;
+    while game.turn_phase != TurnPhase::GameOver && game.round < max_rounds {
+        let player_id = game.current_turn;
+        if last_turn != Some((game.round, player_id)) {
+            last_turn = Some((game.round, player_id));
+            on_turn_start(game);
+        }
+        let state = game.get_game_state();
+        if state.possible_actions.is_empty() {
+            break;
+        }
+
+        let action = bots[player_id].choose_action(&state);
+        if let Err(e) = game.apply_action(player_id, &action) {
+            eprintln!(
+                "Bot '{}' for player {} failed to apply {:?}: {}",
+                bots[player_id].name(),
+                player_id,
+                action,
+                e
+            );
+            if game.apply_action(player_id, &default_action(&state)).is_err() {
+                break;
+            }
+        }
+        actions += 1;
+    }
+
+    GameResult {
+        winner: (game.turn_phase == TurnPhase::GameOver).then_some(game.current_turn),
+        rounds: game.round,
+        actions,
+        elimination_order: game.defeated_players.clone(),
+    }
+}
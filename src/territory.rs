@@ -1,5 +1,6 @@
 // territory.rs
 use crate::game::Game;
+use crate::layout::TerritoryLayout;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
@@ -8,6 +9,8 @@ pub struct Territory {
     pub name: String,
     pub continent: String,
     pub adjacent_territories: HashSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<TerritoryLayout>,
 }
 
 impl Territory {
@@ -16,6 +19,7 @@ impl Territory {
             name: name.to_string(),
             continent: continent.to_string(),
             adjacent_territories: HashSet::new(),
+            layout: None,
         }
     }
 
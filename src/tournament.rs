@@ -0,0 +1,266 @@
+// tournament.rs
+use crate::bot::{BotRegistry, Seat};
+use crate::game::Game;
+use crate::map::{MAX_PLAYERS, MIN_PLAYERS};
+use crate::simulation::{play_game, GameResult};
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const INITIAL_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    // Every combination of bots meets at a table
+    RoundRobin,
+    // Each round bots with similar ratings share a table
+    Swiss { rounds: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentConfig {
+    pub format: Format,
+    pub bots: Vec<String>,
+    pub players_per_game: usize,
+    // Games played by each table on the same deal, the seating rotates every game
+    pub games_per_match: usize,
+    // Deal of the first table, the following tables use the next seeds
+    pub seed: u64,
+    pub max_rounds: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub index: usize,
+    pub round: usize,
+    pub seed: u64,
+    // Bot names in seat order
+    pub seats: Vec<String>,
+    pub result: GameResult,
+}
+
+impl GameRecord {
+    pub fn winner(&self) -> Option<&str> {
+        self.result.winner.map(|seat| self.seats[seat].as_str())
+    }
+
+    pub fn placements(&self) -> Vec<usize> {
+        (0..self.seats.len())
+            .map(|seat| self.result.placement(seat, self.seats.len()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Standing {
+    pub bot: String,
+    pub rating: f64,
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub average_placement: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentResult {
+    pub leaderboard: Vec<Standing>,
+    pub games: Vec<GameRecord>,
+}
+
+// Multiplayer Elo: a game counts as a match between every pair of seats, the better
+// placement wins. The K factor is shared out so a game moves a rating as much as one duel.
+#[derive(Debug, Clone, Default)]
+struct Ratings {
+    ratings: HashMap<String, f64>,
+}
+
+impl Ratings {
+    fn get(&self, bot: &str) -> f64 {
+        self.ratings.get(bot).copied().unwrap_or(INITIAL_RATING)
+    }
+
+    fn update(&mut self, record: &GameRecord) {
+        let placements = record.placements();
+        let num_players = record.seats.len();
+        if num_players < 2 {
+            return;
+        }
+
+        let k = K_FACTOR / (num_players - 1) as f64;
+        let mut changes = vec![0.0; num_players];
+        for (a, b) in (0..num_players).tuple_combinations() {
+            let (rating_a, rating_b) = (self.get(&record.seats[a]), self.get(&record.seats[b]));
+            let expected_a = 1.0 / (1.0 + 10f64.powf((rating_b - rating_a) / 400.0));
+            let score_a = match placements[a].cmp(&placements[b]) {
+                std::cmp::Ordering::Less => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Greater => 0.0,
+            };
+            changes[a] += k * (score_a - expected_a);
+            changes[b] -= k * (score_a - expected_a);
+        }
+
+        // The same bot can sit on several seats, all of its changes apply
+        for (seat, change) in changes.into_iter().enumerate() {
+            *self
+                .ratings
+                .entry(record.seats[seat].clone())
+                .or_insert(INITIAL_RATING) += change;
+        }
+    }
+}
+
+pub fn run_tournament(
+    config: &TournamentConfig,
+    registry: &BotRegistry,
+) -> Result<TournamentResult, String> {
+    if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&config.players_per_game) {
+        return Err(format!("A game needs {} to {} players", MIN_PLAYERS, MAX_PLAYERS));
+    }
+    if let Some(bot) = config.bots.iter().duplicates().next() {
+        return Err(format!("Bot '{}' is entered twice", bot));
+    }
+    if config.bots.len() < config.players_per_game {
+        return Err(format!(
+            "{} bots are not enough for {} players per game",
+            config.bots.len(),
+            config.players_per_game
+        ));
+    }
+    let seats: Vec<Seat> = config.bots.iter().map(|name| Seat::Bot(name.clone())).collect();
+    registry.create_seats(&seats)?;
+
+    let mut ratings = Ratings::default();
+    let mut games = vec![];
+    match config.format {
+        Format::RoundRobin => {
+            let tables: Vec<Vec<String>> = config
+                .bots
+                .iter()
+                .cloned()
+                .combinations(config.players_per_game)
+                .collect();
+            play_round(config, registry, &tables, 0, &mut games, &mut ratings);
+        }
+        Format::Swiss { rounds } => {
+            for round in 0..rounds {
+                let tables = swiss_tables(config, &ratings);
+                play_round(config, registry, &tables, round, &mut games, &mut ratings);
+            }
+        }
+    }
+
+    Ok(TournamentResult {
+        leaderboard: leaderboard(&config.bots, &games, &ratings),
+        games,
+    })
+}
+
+// Sort by rating and seat neighbours together. A remainder too small for a game sits out.
+fn swiss_tables(config: &TournamentConfig, ratings: &Ratings) -> Vec<Vec<String>> {
+    let ranked: Vec<String> = config
+        .bots
+        .iter()
+        .cloned()
+        .sorted_by(|a, b| ratings.get(b).total_cmp(&ratings.get(a)))
+        .collect();
+    ranked
+        .chunks(config.players_per_game)
+        .filter(|table| table.len() >= 2)
+        .map(|table| table.to_vec())
+        .collect()
+}
+
+// Games are played in parallel, ratings are updated afterwards in game order. The bots draw
+// from their own rng and think against the clock, so only the deals follow the seed.
+fn play_round(
+    config: &TournamentConfig,
+    registry: &BotRegistry,
+    tables: &[Vec<String>],
+    round: usize,
+    games: &mut Vec<GameRecord>,
+    ratings: &mut Ratings,
+) {
+    let first_index = games.len();
+    // Every table plays games_per_match games, so this numbers the tables across rounds
+    let first_table = first_index / config.games_per_match.max(1);
+    // All games of a table share its deal, so the rotation only changes who sits where
+    let schedule: Vec<(usize, u64, Vec<String>)> = tables
+        .iter()
+        .enumerate()
+        .flat_map(|(table_index, table)| {
+            let seed = config.seed.wrapping_add((first_table + table_index) as u64);
+            (0..config.games_per_match).map(move |game| {
+                let mut seats = table.clone();
+                seats.rotate_left(game % table.len());
+                (seed, seats)
+            })
+        })
+        .enumerate()
+        .map(|(offset, (seed, seats))| (first_index + offset, seed, seats))
+        .collect();
+
+    let records: Vec<GameRecord> = schedule
+        .into_par_iter()
+        .map(|(index, seed, seats)| {
+            let mut bots: Vec<_> = seats
+                .iter()
+                .map(|name| registry.create(name).expect("Bot was validated"))
+                .collect();
+            let mut game = Game::new_seeded(None, Some(seats.len()), seed);
+            let result = play_game(&mut game, &mut bots, config.max_rounds);
+            GameRecord {
+                index,
+                round,
+                seed,
+                seats,
+                result,
+            }
+        })
+        .collect();
+
+    for record in &records {
+        ratings.update(record);
+    }
+    games.extend(records);
+}
+
+fn leaderboard(bots: &[String], games: &[GameRecord], ratings: &Ratings) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = bots
+        .iter()
+        .map(|bot| {
+            let mut played = 0;
+            let mut wins = 0;
+            let mut draws = 0;
+            let mut placements = 0;
+            for record in games {
+                let placement = record.placements();
+                for (seat, name) in record.seats.iter().enumerate() {
+                    if name != bot {
+                        continue;
+                    }
+                    played += 1;
+                    placements += placement[seat];
+                    match record.result.winner {
+                        Some(winner) if winner == seat => wins += 1,
+                        None => draws += 1,
+                        _ => {}
+                    }
+                }
+            }
+            Standing {
+                bot: bot.clone(),
+                rating: ratings.get(bot),
+                games: played,
+                wins,
+                draws,
+                average_placement: placements as f64 / played.max(1) as f64,
+            }
+        })
+        .collect();
+    standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+    standings
+}
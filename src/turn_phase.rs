@@ -21,10 +21,8 @@ impl Game {
 
     pub fn advance_phase(&mut self) {
         match self.turn_phase {
-            TurnPhase::Reinforce => {
-                if self.reinforcement_armies == 0 {
-                    self.turn_phase = TurnPhase::Attack;
-                }
+            TurnPhase::Reinforce if self.reinforcement_armies == 0 => {
+                self.turn_phase = TurnPhase::Attack;
             }
             TurnPhase::Attack => {
                 self.turn_phase = TurnPhase::Fortify;
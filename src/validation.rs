@@ -0,0 +1,535 @@
+// validation.rs
+use crate::game_config::GameConfig;
+use crate::map::{MapDefinition, MAX_PLAYERS, MIN_PLAYERS};
+use crate::turn_phase::TurnPhase;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// Problems found in a map or scenario. Validation collects all of them instead of stopping
+// at the first, so a map author can fix a file in one go.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationError {
+    // The scenario references a map that can't be found or read
+    UnresolvedMap { reason: String },
+    EmptyMap,
+    DuplicateTerritory { territory: String },
+    DuplicateContinent { continent: String },
+    SelfAdjacent { territory: String },
+    UnknownAdjacentTerritory { territory: String, adjacent: String },
+    AsymmetricAdjacency { territory: String, adjacent: String },
+    UnknownContinent { territory: String, continent: String },
+    UnknownContinentTerritory { continent: String, territory: String },
+    // The territory names a continent that does not list it
+    TerritoryMissingFromContinent { territory: String, continent: String },
+    // A continent lists a territory that names another continent
+    TerritoryInOtherContinent { territory: String, continent: String, declared: String },
+    EmptyContinent { continent: String },
+    // Every group of territories that cannot reach the others, largest first
+    Disconnected { components: Vec<Vec<String>> },
+    // An outline needs at least three points
+    InvalidPolygon { territory: String, points: usize },
+    // The center lies outside the width and height of the map layout
+    CenterOutsideLayout { territory: String },
+    // The starting army table has an entry for an unsupported player count
+    StartingArmiesPlayerCount { players: usize },
+    // Fewer starting armies than territories a player can be dealt
+    TooFewStartingArmies { players: usize, armies: u16, required: u16 },
+    PlayerCount { players: usize },
+    PlayerIdMismatch { index: usize, id: usize },
+    DuplicateAssignment { territory: String, players: Vec<usize> },
+    UnassignedTerritory { territory: String },
+    UnknownPlayerTerritory { player: usize, territory: String },
+    NoArmies { player: usize, territory: String },
+    UnknownCardTerritory { player: usize, territory: String },
+    // A player that owns nothing has to be listed as defeated
+    PlayerWithoutTerritories { player: usize },
+    StateWithoutPlayers,
+    UnknownDefeatedPlayer { player: usize },
+    DefeatedPlayerOwnsTerritories { player: usize },
+    InvalidCurrentTurn { player: usize },
+    // A card in the deck or discard pile
+    UnknownPileCardTerritory { territory: String },
+    // The card of a territory is in several hands or piles
+    DuplicateCard { territory: String },
+    MissingLastAttack,
+    InvalidLastAttack { from: String, to: String },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnresolvedMap { reason } => write!(f, "{}", reason),
+            Self::EmptyMap => write!(f, "The map has no territories"),
+            Self::DuplicateTerritory { territory } => {
+                write!(f, "Territory {} is defined more than once", territory)
+            }
+            Self::DuplicateContinent { continent } => {
+                write!(f, "Continent {} is defined more than once", continent)
+            }
+            Self::SelfAdjacent { territory } => write!(f, "{} is adjacent to itself", territory),
+            Self::UnknownAdjacentTerritory { territory, adjacent } => {
+                write!(f, "{} is adjacent to unknown territory {}", territory, adjacent)
+            }
+            Self::AsymmetricAdjacency { territory, adjacent } => write!(
+                f,
+                "{} is adjacent to {} but not the other way around",
+                territory, adjacent
+            ),
+            Self::UnknownContinent { territory, continent } => {
+                write!(f, "{} belongs to unknown continent {}", territory, continent)
+            }
+            Self::UnknownContinentTerritory { continent, territory } => {
+                write!(f, "Continent {} lists unknown territory {}", continent, territory)
+            }
+            Self::TerritoryMissingFromContinent { territory, continent } => write!(
+                f,
+                "{} belongs to {} but is not in its territory list",
+                territory, continent
+            ),
+            Self::TerritoryInOtherContinent { territory, continent, declared } => write!(
+                f,
+                "Continent {} lists {}, which belongs to {}",
+                continent, territory, declared
+            ),
+            Self::EmptyContinent { continent } => {
+                write!(f, "Continent {} has no territories", continent)
+            }
+            Self::Disconnected { components } => {
+                // The largest part comes first, list the ones cut off from it
+                let groups: Vec<String> =
+                    components.iter().skip(1).map(|c| c.join(", ")).collect();
+                write!(
+                    f,
+                    "The map is split into {} unconnected parts, cut off: [{}]",
+                    components.len(),
+                    groups.join("], [")
+                )
+            }
+            Self::InvalidPolygon { territory, points } => write!(
+                f,
+                "The outline of {} has {} points, it needs at least 3",
+                territory, points
+            ),
+            Self::CenterOutsideLayout { territory } => {
+                write!(f, "The center of {} lies outside the map layout", territory)
+            }
+            Self::StartingArmiesPlayerCount { players } => write!(
+                f,
+                "Starting armies are given for {} players, games have {} to {}",
+                players, MIN_PLAYERS, MAX_PLAYERS
+            ),
+            Self::TooFewStartingArmies { players, armies, required } => write!(
+                f,
+                "{} starting armies for {} players, the territories dealt need at least {}",
+                armies, players, required
+            ),
+            Self::PlayerCount { players } => write!(
+                f,
+                "The scenario has {} players, games have {} to {}",
+                players, MIN_PLAYERS, MAX_PLAYERS
+            ),
+            Self::PlayerIdMismatch { index, id } => {
+                write!(f, "Player {} has id {}, ids must match the player order", index, id)
+            }
+            Self::DuplicateAssignment { territory, players } => {
+                write!(f, "{} is assigned to several players: {:?}", territory, players)
+            }
+            Self::UnassignedTerritory { territory } => {
+                write!(f, "{} is not assigned to any player", territory)
+            }
+            Self::UnknownPlayerTerritory { player, territory } => {
+                write!(f, "Player {} owns unknown territory {}", player, territory)
+            }
+            Self::NoArmies { player, territory } => {
+                write!(f, "Player {} has no armies in {}", player, territory)
+            }
+            Self::UnknownCardTerritory { player, territory } => {
+                write!(f, "Player {} holds a card for unknown territory {}", player, territory)
+            }
+            Self::PlayerWithoutTerritories { player } => {
+                write!(f, "Player {} owns no territories but is not defeated", player)
+            }
+            Self::StateWithoutPlayers => {
+                write!(f, "A game state needs players with their territories")
+            }
+            Self::UnknownDefeatedPlayer { player } => {
+                write!(f, "Defeated player {} does not exist", player)
+            }
+            Self::DefeatedPlayerOwnsTerritories { player } => {
+                write!(f, "Player {} is defeated but still owns territories", player)
+            }
+            Self::InvalidCurrentTurn { player } => {
+                write!(f, "Player {} can't be on turn, there is no such active player", player)
+            }
+            Self::UnknownPileCardTerritory { territory } => {
+                write!(f, "The deck or discard pile has a card for unknown territory {}", territory)
+            }
+            Self::DuplicateCard { territory } => {
+                write!(f, "The card for {} is in play more than once", territory)
+            }
+            Self::MissingLastAttack => {
+                write!(f, "The MoveArmies phase needs the last attack")
+            }
+            Self::InvalidLastAttack { from, to } => write!(
+                f,
+                "The last attack from {} to {} is not between adjacent territories of the current player",
+                from, to
+            ),
+        }
+    }
+}
+
+// One line summary for error messages
+pub fn describe(errors: &[ValidationError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+impl MapDefinition {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        if self.territories.is_empty() {
+            errors.push(ValidationError::EmptyMap);
+            return errors;
+        }
+
+        let mut territories = HashMap::new();
+        for territory in &self.territories {
+            if territories.insert(territory.name.as_str(), territory).is_some() {
+                errors.push(ValidationError::DuplicateTerritory {
+                    territory: territory.name.clone(),
+                });
+            }
+        }
+        let mut continents = HashMap::new();
+        for continent in &self.continents {
+            if continents.insert(continent.name.as_str(), continent).is_some() {
+                errors.push(ValidationError::DuplicateContinent {
+                    continent: continent.name.clone(),
+                });
+            }
+        }
+
+        for territory in &self.territories {
+            for adjacent in &territory.adjacent_territories {
+                if *adjacent == territory.name {
+                    errors.push(ValidationError::SelfAdjacent {
+                        territory: territory.name.clone(),
+                    });
+                    continue;
+                }
+                match territories.get(adjacent.as_str()) {
+                    None => errors.push(ValidationError::UnknownAdjacentTerritory {
+                        territory: territory.name.clone(),
+                        adjacent: adjacent.clone(),
+                    }),
+                    Some(other) if !other.adjacent_territories.contains(&territory.name) => {
+                        errors.push(ValidationError::AsymmetricAdjacency {
+                            territory: territory.name.clone(),
+                            adjacent: adjacent.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            match continents.get(territory.continent.as_str()) {
+                None => errors.push(ValidationError::UnknownContinent {
+                    territory: territory.name.clone(),
+                    continent: territory.continent.clone(),
+                }),
+                Some(continent) if !continent.territories.contains(&territory.name) => {
+                    errors.push(ValidationError::TerritoryMissingFromContinent {
+                        territory: territory.name.clone(),
+                        continent: territory.continent.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        for continent in &self.continents {
+            if continent.territories.is_empty() {
+                errors.push(ValidationError::EmptyContinent {
+                    continent: continent.name.clone(),
+                });
+            }
+            for name in &continent.territories {
+                match territories.get(name.as_str()) {
+                    None => errors.push(ValidationError::UnknownContinentTerritory {
+                        continent: continent.name.clone(),
+                        territory: name.clone(),
+                    }),
+                    Some(territory) if territory.continent != continent.name => {
+                        errors.push(ValidationError::TerritoryInOtherContinent {
+                            territory: name.clone(),
+                            continent: continent.name.clone(),
+                            declared: territory.continent.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let components = self.components();
+        if components.len() > 1 {
+            errors.push(ValidationError::Disconnected { components });
+        }
+        self.validate_starting_armies(&mut errors);
+        self.validate_layout(&mut errors);
+        errors
+    }
+
+    fn validate_starting_armies(&self, errors: &mut Vec<ValidationError>) {
+        for (&players, &armies) in &self.starting_armies {
+            if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players) {
+                errors.push(ValidationError::StartingArmiesPlayerCount { players });
+                continue;
+            }
+            // Player counts above the number of territories can't be played anyway
+            if players > self.territories.len() {
+                continue;
+            }
+            let required = self.territories.len().div_ceil(players) as u16;
+            if armies < required {
+                errors.push(ValidationError::TooFewStartingArmies {
+                    players,
+                    armies,
+                    required,
+                });
+            }
+        }
+    }
+
+    fn validate_layout(&self, errors: &mut Vec<ValidationError>) {
+        let (width, height) = match &self.layout {
+            Some(layout) => (layout.width, layout.height),
+            None => (None, None),
+        };
+        for territory in &self.territories {
+            let Some(layout) = &territory.layout else {
+                continue;
+            };
+            if !layout.polygon.is_empty() && layout.polygon.len() < 3 {
+                errors.push(ValidationError::InvalidPolygon {
+                    territory: territory.name.clone(),
+                    points: layout.polygon.len(),
+                });
+            }
+            if let Some(center) = layout.center {
+                let outside = |value: f64, limit: Option<f64>| {
+                    value < 0.0 || limit.is_some_and(|limit| value > limit)
+                };
+                if outside(center.x, width) || outside(center.y, height) {
+                    errors.push(ValidationError::CenterOutsideLayout {
+                        territory: territory.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    // Connected groups of territories, following adjacency in either direction so a
+    // one-sided link is reported as asymmetric rather than as a split map
+    fn components(&self) -> Vec<Vec<String>> {
+        let mut neighbors: HashMap<&str, Vec<&str>> = HashMap::new();
+        for territory in &self.territories {
+            neighbors.entry(territory.name.as_str()).or_default();
+            for adjacent in &territory.adjacent_territories {
+                if self.territories.iter().any(|t| t.name == *adjacent) {
+                    neighbors.entry(territory.name.as_str()).or_default().push(adjacent);
+                    neighbors.entry(adjacent.as_str()).or_default().push(&territory.name);
+                }
+            }
+        }
+
+        let mut names: Vec<&str> = neighbors.keys().copied().collect();
+        names.sort();
+        let mut seen = HashSet::new();
+        let mut components = vec![];
+        for start in names {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut component = vec![start.to_string()];
+            let mut stack = vec![start];
+            while let Some(current) = stack.pop() {
+                for &next in &neighbors[current] {
+                    if seen.insert(next) {
+                        component.push(next.to_string());
+                        stack.push(next);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        components
+    }
+}
+
+impl GameConfig {
+    // Checks the map and, when the scenario places players, their starting positions
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let map = match self.map_definition() {
+            Ok(map) => map,
+            Err(reason) => return vec![ValidationError::UnresolvedMap { reason }],
+        };
+        let mut errors = map.validate();
+        if self.players.is_empty() {
+            if self.state.is_some() {
+                errors.push(ValidationError::StateWithoutPlayers);
+            }
+            return errors;
+        }
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&self.players.len()) {
+            errors.push(ValidationError::PlayerCount {
+                players: self.players.len(),
+            });
+        }
+
+        let known: HashSet<&str> = map.territories.iter().map(|t| t.name.as_str()).collect();
+        // The armies for a territory that was just conquered haven't moved in yet
+        let conquered = self
+            .state
+            .as_ref()
+            .filter(|state| state.turn_phase == TurnPhase::MoveArmies)
+            .and_then(|state| state.last_attack.as_ref())
+            .map(|attack| attack.to.as_str());
+        let mut owners: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, player) in self.players.iter().enumerate() {
+            if player.id != index {
+                errors.push(ValidationError::PlayerIdMismatch {
+                    index,
+                    id: player.id,
+                });
+            }
+            for territory in &player.territories {
+                if !known.contains(territory.name.as_str()) {
+                    errors.push(ValidationError::UnknownPlayerTerritory {
+                        player: player.id,
+                        territory: territory.name.clone(),
+                    });
+                    continue;
+                }
+                owners.entry(territory.name.as_str()).or_default().push(player.id);
+                if territory.armies == 0 && Some(territory.name.as_str()) != conquered {
+                    errors.push(ValidationError::NoArmies {
+                        player: player.id,
+                        territory: territory.name.clone(),
+                    });
+                }
+            }
+            for card in &player.cards {
+                if let Some(territory) = &card.territory {
+                    if !known.contains(territory.as_str()) {
+                        errors.push(ValidationError::UnknownCardTerritory {
+                            player: player.id,
+                            territory: territory.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        for territory in &map.territories {
+            match owners.get(territory.name.as_str()) {
+                None => errors.push(ValidationError::UnassignedTerritory {
+                    territory: territory.name.clone(),
+                }),
+                Some(players) if players.len() > 1 => {
+                    errors.push(ValidationError::DuplicateAssignment {
+                        territory: territory.name.clone(),
+                        players: players.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        let defeated = self.state.as_ref().map_or(&[][..], |state| &state.defeated_players[..]);
+        for player in &self.players {
+            if player.territories.is_empty() && !defeated.contains(&player.id) {
+                errors.push(ValidationError::PlayerWithoutTerritories { player: player.id });
+            }
+        }
+        self.validate_state(&map, &known, &mut errors);
+        errors
+    }
+
+    fn validate_state(
+        &self,
+        map: &MapDefinition,
+        known: &HashSet<&str>,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let Some(state) = &self.state else {
+            return;
+        };
+
+        for &player in &state.defeated_players {
+            match self.players.get(player) {
+                None => errors.push(ValidationError::UnknownDefeatedPlayer { player }),
+                Some(config) if !config.territories.is_empty() => {
+                    errors.push(ValidationError::DefeatedPlayerOwnsTerritories { player })
+                }
+                Some(_) => {}
+            }
+        }
+        if state.current_turn >= self.players.len()
+            || state.defeated_players.contains(&state.current_turn)
+        {
+            errors.push(ValidationError::InvalidCurrentTurn {
+                player: state.current_turn,
+            });
+        }
+
+        let piles = state.deck.iter().flatten().chain(&state.discard_pile);
+        for card in piles.clone() {
+            if let Some(territory) = &card.territory {
+                if !known.contains(territory.as_str()) {
+                    errors.push(ValidationError::UnknownPileCardTerritory {
+                        territory: territory.clone(),
+                    });
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        let hands = self.players.iter().flat_map(|player| player.cards.iter());
+        for card in hands.chain(piles) {
+            if let Some(territory) = &card.territory {
+                if !seen.insert(territory.as_str()) {
+                    errors.push(ValidationError::DuplicateCard {
+                        territory: territory.clone(),
+                    });
+                }
+            }
+        }
+
+        if state.turn_phase == TurnPhase::MoveArmies {
+            match &state.last_attack {
+                None => errors.push(ValidationError::MissingLastAttack),
+                Some(attack) => {
+                    let owned = self.players.get(state.current_turn).is_some_and(|player| {
+                        [&attack.from, &attack.to]
+                            .iter()
+                            .all(|name| player.territories.iter().any(|t| t.name == **name))
+                    });
+                    let adjacent = map
+                        .territories
+                        .iter()
+                        .any(|t| t.name == attack.from && t.adjacent_territories.contains(&attack.to));
+                    if !owned || !adjacent {
+                        errors.push(ValidationError::InvalidLastAttack {
+                            from: attack.from.clone(),
+                            to: attack.to.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}